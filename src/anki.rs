@@ -0,0 +1,695 @@
+use crate::error::{Error, Result};
+use crate::scheduler::{INITIAL_EASE_FACTOR, MIN_EASE_FACTOR, Schedule};
+use crate::spreadsheet::{Columns, find_header, resolve_column};
+use crate::temp::TempFile;
+use crate::word::{Sense, Word};
+use chrono::{Days, Local, NaiveDate, TimeZone};
+use log::warn;
+use rusqlite::{Connection, params};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Anki のフィールドの区切り文字
+const FIELD_SEPARATOR: char = '\u{1f}';
+/// 書き出すノートタイプの名前
+const NOTE_TYPE_NAME: &str = "vocab basic";
+/// 書き出すノートのフィールドで、例文を囲む要素
+const EXAMPLE_OPEN: &str = "<div class=\"example\">";
+const EXAMPLE_CLOSE: &str = "</div>";
+
+/// Anki のカードの種類 (`cards.type`)
+const CARD_NEW: i64 = 0;
+const CARD_LEARNING: i64 = 1;
+const CARD_REVIEW: i64 = 2;
+const CARD_RELEARNING: i64 = 3;
+
+/// ノートの 1 枚目のカードの学習状況
+struct Card {
+    kind: i64,
+    due: i64,
+    interval: i64,
+    factor: i64,
+    reps: i64,
+    lapses: i64,
+}
+
+/// Anki のパッケージ (`.apkg` / `.colpkg`) からノートを単語として読み込む
+/// 列の指定はノートタイプのフィールド名か 1 始まりの番号で、省略時は 1 番目を英単語、2 番目を日本語訳、`Example` という名前のフィールドを例文にする
+/// ノートの guid を単語の id にし、1 枚目のカードの学習状況を学習スケジュールにする
+/// # Arguments
+/// * `note_type` - 読み込むノートタイプの名前 (省略時は全て)
+pub fn read_package(
+    path: &Path,
+    columns: &Columns,
+    note_type: Option<&str>,
+    today: NaiveDate,
+) -> Result<Vec<Word>> {
+    let collection = extract_collection(path)?;
+    let connection = Connection::open(&collection.0).map_err(|e| package_error(path, e))?;
+    let (crt, models): (i64, String) = connection
+        .query_row("select crt, models from col", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| package_error(path, e))?;
+    let created = Local
+        .timestamp_opt(crt, 0)
+        .single()
+        .map_or(today, |created| created.date_naive());
+    let models: BTreeMap<String, Value> =
+        serde_json::from_str(&models).map_err(|e| package_error(path, e))?;
+
+    let mut statement = connection
+        .prepare(
+            "select n.guid, n.mid, n.flds, n.tags, c.type, c.due, c.ivl, c.factor, c.reps, c.lapses
+             from notes n
+             left join cards c on c.id = (select id from cards where nid = n.id order by ord limit 1)
+             order by n.id",
+        )
+        .map_err(|e| package_error(path, e))?;
+    let notes = statement
+        .query_map([], |row| {
+            let card = match row.get::<_, Option<i64>>(4)? {
+                Some(kind) => Some(Card {
+                    kind,
+                    due: row.get(5)?,
+                    interval: row.get(6)?,
+                    factor: row.get(7)?,
+                    reps: row.get(8)?,
+                    lapses: row.get(9)?,
+                }),
+                None => None,
+            };
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                card,
+            ))
+        })
+        .map_err(|e| package_error(path, e))?;
+
+    let mut mappings = BTreeMap::new();
+    let mut words = Vec::new();
+    let mut skipped = BTreeMap::<String, usize>::new();
+    let mut without_meaning = 0;
+    for note in notes {
+        let (guid, mid, fields, tags, card) = note.map_err(|e| package_error(path, e))?;
+        let model = models.get(&mid.to_string()).unwrap_or(&Value::Null);
+        let name = model["name"].as_str().unwrap_or_default();
+        if note_type.is_some_and(|note_type| note_type != name) {
+            continue;
+        }
+        let mapping = mappings
+            .entry(mid)
+            .or_insert_with(|| field_mapping(path, model, columns));
+        let Ok((english, japanese, example)) = mapping else {
+            *skipped.entry(name.to_string()).or_default() += 1;
+            continue;
+        };
+
+        let fields = fields.split(FIELD_SEPARATOR).collect::<Vec<_>>();
+        let field = |i: usize| fields.get(i).copied().unwrap_or_default();
+        let english = html_to_text(field(*english));
+        if english.is_empty() {
+            continue;
+        }
+        let (meaning, mut examples) = split_examples(field(*japanese));
+        let meaning = html_to_text(&meaning);
+        if meaning.is_empty() {
+            without_meaning += 1;
+            continue;
+        }
+        if let Some(example) = example {
+            examples = vec![field(*example).to_string()];
+        }
+        let examples = examples
+            .iter()
+            .map(|example| html_to_text(example))
+            .filter(|example| !example.is_empty())
+            .collect();
+        words.push(Word {
+            id: guid,
+            english,
+            senses: vec![Sense {
+                meaning,
+                part_of_speech: None,
+                examples,
+                extra: Default::default(),
+            }],
+            tags: tags.split_whitespace().map(str::to_string).collect(),
+            schedule: card.and_then(|card| schedule_of(&card, created, today)),
+            skip: false,
+            extra: Default::default(),
+        });
+    }
+
+    for (name, count) in &skipped {
+        warn!(
+            "Skipped {count} notes of note type \"{name}\", which does not have the requested fields"
+        );
+    }
+    if without_meaning > 0 {
+        warn!("Skipped {without_meaning} notes without a Japanese meaning");
+    }
+    if words.is_empty()
+        && let Some(Err(e)) = mappings.into_values().find(Result::is_err)
+    {
+        return Err(e);
+    }
+    Ok(words)
+}
+
+/// パッケージから SQLite のコレクションを一時ファイルに取り出す
+/// 新しい Anki だけが読める形式 (`collection.anki21b`) には対応しない
+fn extract_collection(path: &Path) -> Result<TempFile> {
+    let file = File::open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| package_error(path, e))?;
+    // 両方ある場合、collection.anki2 は古い Anki 向けの案内しか含まない
+    let name = ["collection.anki21", "collection.anki2"]
+        .into_iter()
+        .find(|name| archive.index_for_name(name).is_some());
+    let Some(name) = name else {
+        let message = if archive.index_for_name("collection.anki21b").is_some() {
+            "this package uses the compressed format of newer Anki versions (export it again with \"Support older Anki versions\" checked)"
+        } else {
+            "this is not an Anki package"
+        };
+        return Err(package_error(path, message));
+    };
+
+    let collection = TempFile::new("anki2");
+    let mut entry = archive.by_name(name).map_err(|e| package_error(path, e))?;
+    io::copy(&mut entry, &mut File::create(&collection.0)?)?;
+    Ok(collection)
+}
+
+/// ノートタイプのフィールドのうち、英単語・日本語訳・例文にするものの番号
+fn field_mapping(
+    path: &Path,
+    model: &Value,
+    columns: &Columns,
+) -> Result<(usize, usize, Option<usize>)> {
+    let mut fields = model["flds"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|field| {
+            (
+                field["ord"].as_u64().unwrap_or_default(),
+                field["name"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect::<Vec<_>>();
+    fields.sort_by_key(|(ord, _)| *ord);
+    let names = fields.into_iter().map(|(_, name)| name).collect::<Vec<_>>();
+
+    let column = |spec: Option<&str>, name: &str, position: usize| match spec {
+        Some(spec) => resolve_column(path, Some(names.as_slice()), Some(spec), name, position),
+        None => Ok(Some(position)),
+    };
+    let english = column(columns.english.as_deref(), "english", 0)?.unwrap_or(0);
+    let japanese = column(columns.japanese.as_deref(), "japanese", 1)?.unwrap_or(1);
+    let example = match columns.example.as_deref() {
+        Some(spec) => resolve_column(path, Some(names.as_slice()), Some(spec), "example", 2)?,
+        None => find_header(&names, "example"),
+    };
+    Ok((english, japanese, example))
+}
+
+/// カードの学習状況を学習スケジュールにする (未学習のカードは None)
+/// # Arguments
+/// * `created` - コレクションを作った日 (復習カードの出題日の基準)
+fn schedule_of(card: &Card, created: NaiveDate, today: NaiveDate) -> Option<Schedule> {
+    let ease_factor = if card.factor > 0 {
+        (card.factor as f64 / 1000.0).max(MIN_EASE_FACTOR)
+    } else {
+        INITIAL_EASE_FACTOR
+    };
+    match card.kind {
+        CARD_REVIEW => Some(Schedule {
+            ease_factor,
+            interval: card.interval.clamp(1, u32::MAX as i64) as u32,
+            repetitions: (card.reps - card.lapses).clamp(1, u32::MAX as i64) as u32,
+            due: created + Days::new(card.due.max(0) as u64),
+            last_grade: None,
+        }),
+        CARD_LEARNING | CARD_RELEARNING => Some(Schedule {
+            ease_factor,
+            ..Schedule::new(today)
+        }),
+        _ => None,
+    }
+}
+
+/// 単語帳を Anki のパッケージ (`.apkg`) に書き出す
+/// 英単語を表、日本語訳と例文を裏にした 2 つのフィールドのノートにし、id をノートの guid にする
+/// 復習間隔が 1 日以上の単語は復習カード、それ以外は新規カードにする
+pub fn write_package(path: &Path, deck_name: &str, words: &[Word], today: NaiveDate) -> Result<()> {
+    let collection = TempFile::new("anki2");
+    let connection = Connection::open(&collection.0).map_err(export_error)?;
+    connection.execute_batch(SCHEMA).map_err(export_error)?;
+
+    // 復習の期限切れのカードも、コレクションを作った日より後になるようにする
+    let created = words
+        .iter()
+        .filter_map(|word| word.schedule.as_ref().map(|schedule| schedule.due))
+        .fold(today, NaiveDate::min);
+    let crt = Local
+        .from_local_datetime(&created.and_hms_opt(0, 0, 0).unwrap_or_default())
+        .earliest()
+        .map_or(0, |created| created.timestamp());
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64);
+    let (model_id, deck_id) = (now, now + 1);
+    connection
+        .execute(
+            "insert into col values (1, ?1, ?2, ?3, 11, 0, 0, 0, ?4, ?5, ?6, ?7, '{}')",
+            params![
+                crt,
+                now / 1000,
+                now,
+                collection_config(model_id, deck_id).to_string(),
+                json!({ model_id.to_string(): note_type(model_id, deck_id, now / 1000) })
+                    .to_string(),
+                decks(deck_id, deck_name, now / 1000).to_string(),
+                deck_configs(now / 1000).to_string(),
+            ],
+        )
+        .map_err(export_error)?;
+
+    for (i, word) in words.iter().enumerate() {
+        let id = now + i as i64;
+        let front = escape_html(&word.english);
+        let mut back = escape_html(&word.meaning());
+        for example in word.examples() {
+            back.push_str(&format!(
+                "<br>{EXAMPLE_OPEN}{}{EXAMPLE_CLOSE}",
+                escape_html(example)
+            ));
+        }
+        connection
+            .execute(
+                "insert into notes values (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+                params![
+                    id,
+                    word.id,
+                    model_id,
+                    now / 1000,
+                    anki_tags(&word.tags),
+                    format!("{front}{FIELD_SEPARATOR}{back}"),
+                    word.english,
+                    checksum(&word.english),
+                ],
+            )
+            .map_err(export_error)?;
+
+        let (kind, due, interval, factor, reps) = match &word.schedule {
+            Some(schedule) if schedule.interval > 0 => (
+                CARD_REVIEW,
+                (schedule.due - created).num_days(),
+                schedule.interval,
+                (schedule.ease_factor * 1000.0).round() as i64,
+                schedule.repetitions,
+            ),
+            _ => (CARD_NEW, i as i64 + 1, 0, 0, 0),
+        };
+        connection
+            .execute(
+                "insert into cards values (?1, ?2, ?3, 0, ?4, -1, ?5, ?5, ?6, ?7, ?8, ?9, 0, 0, 0, 0, 0, '')",
+                params![id, id, deck_id, now / 1000, kind, due, interval, factor, reps],
+            )
+            .map_err(export_error)?;
+    }
+    drop(connection);
+
+    let mut package = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    package
+        .start_file("collection.anki2", options)
+        .map_err(export_error)?;
+    package.write_all(&fs::read(&collection.0)?)?;
+    package.start_file("media", options).map_err(export_error)?;
+    package.write_all(b"{}")?;
+    let package = package.finish().map_err(export_error)?;
+    fs::write(path, package.into_inner())?;
+    Ok(())
+}
+
+fn package_error(path: &Path, error: impl Display) -> Error {
+    Error::Import {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    }
+}
+
+fn export_error(error: impl Display) -> Error {
+    io::Error::other(format!("failed to write the Anki package: {error}")).into()
+}
+
+/// Anki のノートのタグ (空白区切りで前後にも空白を置く。タグ中の空白は `_` にする)
+fn anki_tags(tags: &[String]) -> String {
+    if tags.is_empty() {
+        return String::new();
+    }
+    let tags = tags
+        .iter()
+        .map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("_"))
+        .collect::<Vec<_>>();
+    format!(" {} ", tags.join(" "))
+}
+
+/// Anki がノートの重複の検出に使うチェックサム (ソートフィールドの SHA-1 の先頭 32 ビット)
+fn checksum(text: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(text).digest().bytes();
+    i64::from(u32::from_be_bytes([
+        digest[0], digest[1], digest[2], digest[3],
+    ]))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// フィールドから `vocab export` が書き出した例文の要素を取り除く
+/// # Returns
+/// 例文を取り除いたフィールドと、例文のリスト
+fn split_examples(field: &str) -> (String, Vec<String>) {
+    let mut rest = String::new();
+    let mut examples = Vec::new();
+    let mut remaining = field;
+    while let Some(start) = remaining.find(EXAMPLE_OPEN) {
+        rest.push_str(&remaining[..start]);
+        let after = &remaining[start + EXAMPLE_OPEN.len()..];
+        let end = after.find(EXAMPLE_CLOSE).unwrap_or(after.len());
+        examples.push(after[..end].to_string());
+        remaining = after.get(end + EXAMPLE_CLOSE.len()..).unwrap_or_default();
+    }
+    rest.push_str(remaining);
+    (rest, examples)
+}
+
+/// フィールドの HTML をテキストにする
+/// タグと音声の指定 (`[sound:...]`) を取り除き、文字参照を戻し、空白をまとめる
+fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find(['<', '[']) {
+        text.push_str(&rest[..start]);
+        let (close, tag) = if rest[start..].starts_with('<') {
+            ('>', true)
+        } else if rest[start..].starts_with("[sound:") {
+            (']', false)
+        } else {
+            text.push('[');
+            rest = &rest[start + 1..];
+            continue;
+        };
+        let end = rest[start..]
+            .find(close)
+            .map_or(rest.len(), |end| start + end + 1);
+        // 改行やブロック要素の区切りは空白にする
+        if tag {
+            text.push(' ');
+        }
+        rest = &rest[end..];
+    }
+    text.push_str(rest);
+    decode_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let character = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => name.strip_prefix('#').and_then(|code| code.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+        match (character, entity) {
+            (Some(character), Some((_, end))) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Anki 2.1 の古い形式 (スキーマ 11) のコレクション
+const SCHEMA: &str = "
+create table col (
+    id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+    ver integer not null, dty integer not null, usn integer not null, ls integer not null,
+    conf text not null, models text not null, decks text not null, dconf text not null,
+    tags text not null
+);
+create table notes (
+    id integer primary key, guid text not null, mid integer not null, mod integer not null,
+    usn integer not null, tags text not null, flds text not null, sfld integer not null,
+    csum integer not null, flags integer not null, data text not null
+);
+create table cards (
+    id integer primary key, nid integer not null, did integer not null, ord integer not null,
+    mod integer not null, usn integer not null, type integer not null, queue integer not null,
+    due integer not null, ivl integer not null, factor integer not null, reps integer not null,
+    lapses integer not null, left integer not null, odue integer not null, odid integer not null,
+    flags integer not null, data text not null
+);
+create table revlog (
+    id integer primary key, cid integer not null, usn integer not null, ease integer not null,
+    ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null,
+    type integer not null
+);
+create table graves (usn integer not null, oid integer not null, type integer not null);
+create index ix_notes_usn on notes (usn);
+create index ix_cards_usn on cards (usn);
+create index ix_revlog_usn on revlog (usn);
+create index ix_cards_nid on cards (nid);
+create index ix_cards_sched on cards (did, queue, due);
+create index ix_revlog_cid on revlog (cid);
+create index ix_notes_csum on notes (csum);
+";
+
+fn collection_config(model_id: i64, deck_id: i64) -> Value {
+    json!({
+        "activeDecks": [deck_id],
+        "curDeck": deck_id,
+        "curModel": model_id.to_string(),
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true,
+    })
+}
+
+/// 英単語を表、日本語訳と例文を裏にしたノートタイプ
+fn note_type(model_id: i64, deck_id: i64, modified: i64) -> Value {
+    let field = |name: &str, ord: usize| {
+        json!({
+            "name": name,
+            "ord": ord,
+            "sticky": false,
+            "rtl": false,
+            "font": "Arial",
+            "size": 20,
+            "media": [],
+        })
+    };
+    json!({
+        "id": model_id,
+        "name": NOTE_TYPE_NAME,
+        "type": 0,
+        "mod": modified,
+        "usn": -1,
+        "sortf": 0,
+        "did": deck_id,
+        "flds": [field("Front", 0), field("Back", 1)],
+        "tmpls": [{
+            "name": "Card 1",
+            "ord": 0,
+            "qfmt": "{{Front}}",
+            "afmt": "{{FrontSide}}\n\n<hr id=answer>\n\n{{Back}}",
+            "bqfmt": "",
+            "bafmt": "",
+            "did": null,
+        }],
+        "css": ".card { font-family: arial; font-size: 20px; text-align: center; }\n.example { font-style: italic; margin-top: 0.5em; }",
+        "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+        "latexPost": "\\end{document}",
+        "tags": [],
+        "vers": [],
+        "req": [[0, "any", [0]]],
+    })
+}
+
+fn decks(deck_id: i64, deck_name: &str, modified: i64) -> Value {
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id,
+            "name": name,
+            "mod": modified,
+            "usn": -1,
+            "desc": "",
+            "dyn": 0,
+            "conf": 1,
+            "collapsed": false,
+            "browserCollapsed": false,
+            "extendNew": 10,
+            "extendRev": 50,
+            "newToday": [0, 0],
+            "revToday": [0, 0],
+            "lrnToday": [0, 0],
+            "timeToday": [0, 0],
+        })
+    };
+    json!({
+        "1": deck(1, "Default"),
+        deck_id.to_string(): deck(deck_id, deck_name),
+    })
+}
+
+fn deck_configs(modified: i64) -> Value {
+    json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "mod": modified,
+            "usn": -1,
+            "maxTaken": 60,
+            "autoplay": true,
+            "timer": 0,
+            "replayq": true,
+            "dyn": false,
+            "new": {
+                "delays": [1, 10],
+                "ints": [1, 4, 7],
+                "initialFactor": 2500,
+                "order": 1,
+                "perDay": 20,
+                "bury": true,
+                "separate": true,
+            },
+            "lapse": {
+                "delays": [10],
+                "mult": 0,
+                "minInt": 1,
+                "leechFails": 8,
+                "leechAction": 0,
+            },
+            "rev": {
+                "perDay": 200,
+                "ease4": 1.3,
+                "fuzz": 0.05,
+                "minSpace": 1,
+                "ivlFct": 1,
+                "maxIvl": 36500,
+                "bury": true,
+                "hardFactor": 1.2,
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::Grade;
+
+    #[test]
+    fn test_html_to_text() {
+        assert_eq!(
+            html_to_text("<b>run</b>&nbsp;[sound:run.mp3]<br>走る &amp; 駆ける [1]"),
+            "run 走る & 駆ける [1]"
+        );
+        assert_eq!(decode_entities("&#x41;&#66;&unknown; &"), "AB&unknown; &");
+
+        let (meaning, examples) = split_examples(
+            "走る<br><div class=\"example\">I run.</div><br><div class=\"example\">He ran.</div>",
+        );
+        assert_eq!(html_to_text(&meaning), "走る");
+        assert_eq!(examples, vec!["I run.", "He ran."]);
+    }
+
+    #[test]
+    fn test_write_and_read_package() {
+        let today = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+        let mut words = vec![
+            Word::new("run", "走る <速く>", "I run & swim."),
+            Word::new("eat", "食べる", ""),
+        ];
+        let mut schedule = Schedule::new(today);
+        schedule.grade(Grade::Good, today);
+        schedule.grade(Grade::Good, today);
+        words[0].schedule = Some(schedule.clone());
+        words[0].tags = vec!["toeic".to_string(), "verb".to_string()];
+        // 直近の自己評価は Anki のカードにはない
+        schedule.last_grade = None;
+
+        let file = TempFile::new("apkg");
+        write_package(&file.0, "TOEIC", &words, today).unwrap();
+        let imported = read_package(&file.0, &Columns::default(), None, today).unwrap();
+        let renamed = Columns {
+            english: Some("Back".to_string()),
+            japanese: Some("1".to_string()),
+            example: None,
+        };
+        let swapped = read_package(&file.0, &renamed, Some(NOTE_TYPE_NAME), today).unwrap();
+
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].id, words[0].id);
+        assert_eq!(imported[0].english, "run");
+        assert_eq!(imported[0].meaning(), "走る <速く>");
+        assert_eq!(
+            imported[0].examples().collect::<Vec<_>>(),
+            vec!["I run & swim."]
+        );
+        assert_eq!(imported[0].schedule, Some(schedule));
+        assert_eq!(imported[0].tags, words[0].tags);
+        assert_eq!(imported[1].examples().count(), 0);
+        assert!(imported[1].tags.is_empty());
+        assert_eq!(imported[1].schedule, None);
+        assert_eq!(swapped[1].english, "食べる");
+        assert_eq!(swapped[1].meaning(), "eat");
+    }
+}
@@ -0,0 +1,101 @@
+use crate::matcher::normalize;
+use crate::word::Word;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+
+/// 正解を含めた選択肢の数
+pub const CHOICES: usize = 4;
+
+/// 正解以外の選択肢に使う単語のインデックスを選ぶ
+/// 品詞が同じ単語を優先し、日本語訳が正解や他の選択肢と同じになる単語は除く
+/// # Arguments
+/// * `parts_of_speech` - 単語ごとの品詞の候補 (`words` と同じ順)
+pub fn pick_distractors<R: Rng>(
+    words: &[Word],
+    target: usize,
+    parts_of_speech: &[HashSet<String>],
+    rng: &mut R,
+) -> Vec<usize> {
+    let target_pos = parts_of_speech.get(target);
+    let shares_pos = |i: usize| {
+        target_pos
+            .zip(parts_of_speech.get(i))
+            .is_some_and(|(a, b)| !a.is_disjoint(b))
+    };
+
+    let mut candidates = (0..words.len())
+        .filter(|&i| i != target)
+        .collect::<Vec<_>>();
+    candidates.shuffle(rng);
+    candidates.sort_by_key(|&i| !shares_pos(i));
+
+    let mut meanings = HashSet::from([normalize(&words[target].meaning())]);
+    candidates
+        .into_iter()
+        .filter(|&i| meanings.insert(normalize(&words[i].meaning())))
+        .take(CHOICES - 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn word(english: &str, japanese: &str) -> Word {
+        Word::new(english, japanese, "")
+    }
+
+    fn pos(tags: &[&str]) -> HashSet<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn test_pick_distractors_prefers_same_part_of_speech() {
+        let words = vec![
+            word("eat", "食べる"),
+            word("child", "子供"),
+            word("run", "走る"),
+            word("happy", "幸せな"),
+            word("have", "持つ"),
+            word("dog", "犬"),
+            word("speak", "話す"),
+        ];
+        let parts_of_speech = vec![
+            pos(&["VB"]),
+            pos(&["NN"]),
+            pos(&["VB", "NN"]),
+            pos(&["JJ"]),
+            pos(&["VB"]),
+            pos(&["NN"]),
+            pos(&["VB"]),
+        ];
+
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut distractors = pick_distractors(&words, 0, &parts_of_speech, &mut rng);
+            distractors.sort();
+            assert_eq!(distractors, vec![2, 4, 6]);
+        }
+    }
+
+    #[test]
+    fn test_pick_distractors_skips_same_meaning() {
+        let words = vec![
+            word("child", "子供"),
+            word("kid", "子ども"),
+            word("kids", "子供"),
+            word("dog", "犬"),
+        ];
+        let parts_of_speech = vec![pos(&["NN"]); 4];
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let distractors = pick_distractors(&words, 0, &parts_of_speech, &mut rng);
+
+        assert!(!distractors.contains(&2));
+        assert!(distractors.contains(&1));
+        assert!(distractors.contains(&3));
+    }
+}
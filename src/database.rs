@@ -0,0 +1,611 @@
+use crate::deck::{Deck, validate};
+use crate::error::{Error, Result};
+use crate::history::Event;
+use crate::scheduler::{Grade, Schedule};
+use crate::storage::Storage;
+use crate::word::{Sense, Word};
+use rusqlite::types::Type;
+use rusqlite::{Connection, OpenFlags, TransactionBehavior, params};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// データベースの形式のバージョン (`pragma user_version` に書く)
+const SCHEMA_VERSION: u64 = 1;
+
+/// 別のプロセスが書き込み中の場合に待つ時間
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+create table if not exists deck (
+    key text primary key,
+    value text not null
+);
+create table if not exists words (
+    id text primary key,
+    position integer not null,
+    english text not null,
+    extra text not null default '{}'
+);
+create index if not exists words_english on words (english);
+create table if not exists senses (
+    word_id text not null references words (id) on delete cascade,
+    position integer not null,
+    meaning text not null,
+    part_of_speech text,
+    examples text not null default '[]',
+    extra text not null default '{}',
+    primary key (word_id, position)
+);
+create table if not exists tags (
+    word_id text not null references words (id) on delete cascade,
+    tag text not null,
+    primary key (word_id, tag)
+);
+create table if not exists schedules (
+    word_id text primary key references words (id) on delete cascade,
+    ease_factor real not null,
+    interval integer not null,
+    repetitions integer not null,
+    due text not null,
+    last_grade text
+);
+create index if not exists schedules_due on schedules (due);
+create table if not exists reviews (
+    id integer primary key,
+    word_id text,
+    word text not null,
+    timestamp text not null,
+    action text not null,
+    grade text,
+    response_time_ms integer
+);
+";
+
+/// SQLite の単語帳
+/// 単語帳・学習スケジュール・学習履歴を 1 つのデータベースに保存する
+/// 学習スケジュールは単語ごとに更新するため、大きな単語帳でも保存が速く、複数のプロセスから同時に使える
+pub struct SqliteStorage {
+    path: PathBuf,
+    connection: Option<Connection>,
+    /// 最後に読み込んだか保存した時点の単語 (id ごと)
+    /// 単語帳を保存する際は、これと比べて変更した単語だけを書き込む
+    snapshot: Option<HashMap<String, Word>>,
+}
+
+impl SqliteStorage {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            connection: None,
+            snapshot: None,
+        }
+    }
+
+    /// データベースを開く
+    /// # Arguments
+    /// * `create` - データベースがなければ作るかどうか (作らない場合は `DeckNotFound` になる)
+    fn connect(&mut self, create: bool) -> Result<&mut Connection> {
+        if self.connection.is_none() {
+            if !create && !self.path.exists() {
+                return Err(Error::DeckNotFound {
+                    path: self.path.clone(),
+                });
+            }
+            self.connection = Some(open_database(&self.path)?);
+        }
+        Ok(self
+            .connection
+            .as_mut()
+            .expect("the database was just opened"))
+    }
+
+    /// データベースを操作し、SQLite のエラーをこの単語帳のエラーとして報告する
+    fn with_connection<T>(
+        &mut self,
+        create: bool,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T> {
+        let path = self.path.clone();
+        f(self.connect(create)?).map_err(|e| database_error(&path, e))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&mut self) -> Result<Deck> {
+        let deck = self.with_connection(false, |connection| read_deck(connection))?;
+        validate(&self.path, &deck.words)?;
+        self.snapshot = Some(snapshot_of(&deck.words));
+        Ok(deck)
+    }
+
+    fn inspect(&mut self) -> Result<Deck> {
+        let mut deck = self.with_connection(false, |connection| read_deck(connection))?;
+        deck.words.iter_mut().for_each(|word| word.schedule = None);
+        Ok(deck)
+    }
+
+    fn save(&mut self, deck: &Deck) -> Result<()> {
+        let snapshot = self.snapshot.take();
+        self.with_connection(true, |connection| {
+            write_deck(connection, deck, snapshot.as_ref())
+        })?;
+        self.snapshot = Some(snapshot_of(&deck.words));
+        Ok(())
+    }
+
+    fn save_schedules(&mut self, words: &[Word]) -> Result<()> {
+        self.with_connection(false, |connection| {
+            let transaction = connection.transaction()?;
+            write_schedules(&transaction, words)?;
+            transaction.commit()
+        })?;
+        if let Some(snapshot) = self.snapshot.as_mut() {
+            for word in words {
+                if let Some(saved) = snapshot.get_mut(&word.id) {
+                    saved.schedule = word.schedule.clone();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn history(&mut self) -> Result<Vec<Event>> {
+        self.with_connection(false, |connection| read_reviews(connection))
+    }
+
+    fn record(&mut self, events: &[Event]) -> Result<()> {
+        self.with_connection(true, |connection| {
+            let transaction = connection.transaction()?;
+            write_reviews(&transaction, events)?;
+            transaction.commit()
+        })
+    }
+}
+
+/// vocab の単語帳のデータベースかどうか (`words` 表があるか、表が 1 つもない)
+/// SQLite のデータベースとして開けないファイル (Windows の `Thumbs.db` など) は単語帳とみなさない
+pub fn is_deck_database(path: &Path) -> bool {
+    let Ok(connection) = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) else {
+        return false;
+    };
+    connection
+        .query_row(
+            "select count(*) = 0 or sum(name = 'words') > 0 from sqlite_master where type = 'table'",
+            [],
+            |row| row.get::<_, bool>(0),
+        )
+        .unwrap_or(false)
+}
+
+fn open_database(path: &Path) -> Result<Connection> {
+    let error = |e: rusqlite::Error| database_error(path, e);
+    let connection = Connection::open(path).map_err(error)?;
+    connection.busy_timeout(BUSY_TIMEOUT).map_err(error)?;
+    // WAL にすると、学習中のプロセスが書き込んでいる間も他のプロセスが読み込める
+    connection
+        .execute_batch("pragma journal_mode = wal; pragma foreign_keys = on;")
+        .map_err(error)?;
+
+    let version: u64 = connection
+        .query_row("pragma user_version", [], |row| row.get(0))
+        .map_err(error)?;
+    if version > SCHEMA_VERSION {
+        return Err(Error::UnsupportedVersion {
+            path: path.to_path_buf(),
+            version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    connection.execute_batch(SCHEMA).map_err(error)?;
+    connection
+        .pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(error)?;
+    Ok(connection)
+}
+
+fn database_error(path: &Path, error: rusqlite::Error) -> Error {
+    Error::Database {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    }
+}
+
+fn read_deck(connection: &Connection) -> rusqlite::Result<Deck> {
+    let mut properties = HashMap::new();
+    let mut statement = connection.prepare("select key, value from deck")?;
+    for row in statement.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })? {
+        let (key, value) = row?;
+        properties.insert(key, value);
+    }
+
+    let mut senses = HashMap::<String, Vec<Sense>>::new();
+    let mut statement = connection.prepare(
+        "select word_id, meaning, part_of_speech, examples, extra from senses order by word_id, position",
+    )?;
+    for row in statement.query_map([], |row| {
+        let sense = Sense {
+            meaning: row.get(1)?,
+            part_of_speech: row.get(2)?,
+            examples: from_json(&row.get::<_, String>(3)?)?,
+            extra: from_json(&row.get::<_, String>(4)?)?,
+        };
+        Ok((row.get::<_, String>(0)?, sense))
+    })? {
+        let (id, sense) = row?;
+        senses.entry(id).or_default().push(sense);
+    }
+
+    let mut tags = HashMap::<String, Vec<String>>::new();
+    let mut statement =
+        connection.prepare("select word_id, tag from tags order by word_id, rowid")?;
+    for row in statement.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })? {
+        let (id, tag) = row?;
+        tags.entry(id).or_default().push(tag);
+    }
+
+    let mut schedules = HashMap::new();
+    let mut statement = connection.prepare(
+        "select word_id, ease_factor, interval, repetitions, due, last_grade from schedules",
+    )?;
+    for row in statement.query_map([], |row| {
+        let schedule = Schedule {
+            ease_factor: row.get(1)?,
+            interval: row.get(2)?,
+            repetitions: row.get(3)?,
+            due: from_value(Value::String(row.get(4)?))?,
+            last_grade: row
+                .get::<_, Option<String>>(5)?
+                .map(|grade| from_value(Value::String(grade)))
+                .transpose()?,
+        };
+        Ok((row.get::<_, String>(0)?, schedule))
+    })? {
+        let (id, schedule) = row?;
+        schedules.insert(id, schedule);
+    }
+
+    let mut statement =
+        connection.prepare("select id, english, extra from words order by position, rowid")?;
+    let words = statement
+        .query_map([], |row| {
+            let id = row.get::<_, String>(0)?;
+            Ok(Word {
+                senses: senses.remove(&id).unwrap_or_default(),
+                tags: tags.remove(&id).unwrap_or_default(),
+                schedule: schedules.remove(&id),
+                id,
+                english: row.get(1)?,
+                skip: false,
+                extra: from_json(&row.get::<_, String>(2)?)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Deck {
+        metadata: property(&properties, "metadata")?,
+        words,
+        extra: property(&properties, "extra")?,
+    })
+}
+
+/// `deck` 表の値を読む (なければ既定値)
+fn property<T: DeserializeOwned + Default>(
+    properties: &HashMap<String, String>,
+    key: &str,
+) -> rusqlite::Result<T> {
+    properties
+        .get(key)
+        .map_or_else(|| Ok(T::default()), |value| from_json(value))
+}
+
+fn snapshot_of(words: &[Word]) -> HashMap<String, Word> {
+    words
+        .iter()
+        .map(|word| (word.id.clone(), word.clone()))
+        .collect()
+}
+
+/// 単語帳を保存する
+/// `snapshot` (読み込んだ時点の単語) と比べて、追加・変更・削除した単語と変更した学習スケジュールだけを書き込む
+/// 読み込んだ後に他のプロセスが付けた学習スケジュールや追加した単語は、古い内容で上書きしない
+/// `snapshot` がなければ、今のデータベースの内容を `deck` で置き換える
+fn write_deck(
+    connection: &mut Connection,
+    deck: &Deck,
+    snapshot: Option<&HashMap<String, Word>>,
+) -> rusqlite::Result<()> {
+    // 読み込みから書き込みまでの間に他のプロセスが書き込まないよう、最初から書き込みのロックを取る
+    let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    {
+        let current;
+        let snapshot = match snapshot {
+            Some(snapshot) => snapshot,
+            None => {
+                current = snapshot_of(&read_deck(&transaction)?.words);
+                &current
+            }
+        };
+
+        let mut put =
+            transaction.prepare("insert or replace into deck (key, value) values (?1, ?2)")?;
+        put.execute(params!["metadata", to_json(&deck.metadata)])?;
+        put.execute(params!["extra", to_json(&deck.extra)])?;
+
+        // 意味・タグ・学習スケジュールは単語と一緒に削除される
+        let ids = deck
+            .words
+            .iter()
+            .map(|word| word.id.as_str())
+            .collect::<HashSet<_>>();
+        let mut delete_word = transaction.prepare("delete from words where id = ?1")?;
+        for id in snapshot.keys().filter(|id| !ids.contains(id.as_str())) {
+            delete_word.execute([id])?;
+        }
+
+        let mut positions = HashMap::new();
+        let mut statement = transaction.prepare("select id, position from words")?;
+        for row in statement.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })? {
+            let (id, position) = row?;
+            positions.insert(id, position);
+        }
+
+        let mut upsert_word = transaction.prepare(
+            "insert into words (id, position, english, extra) values (?1, ?2, ?3, ?4)
+             on conflict (id) do update set
+                 position = excluded.position,
+                 english = excluded.english,
+                 extra = excluded.extra",
+        )?;
+        let mut delete_senses = transaction.prepare("delete from senses where word_id = ?1")?;
+        let mut delete_tags = transaction.prepare("delete from tags where word_id = ?1")?;
+        let mut insert_sense = transaction.prepare(
+            "insert into senses (word_id, position, meaning, part_of_speech, examples, extra)
+             values (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        let mut insert_tag =
+            transaction.prepare("insert or ignore into tags (word_id, tag) values (?1, ?2)")?;
+        // 並び順が保たれている単語の位置はそのままにし、それ以外の単語だけ直前の単語の後ろに置く
+        let mut last = -1;
+        for word in &deck.words {
+            let stored = positions.get(&word.id).copied();
+            let position = stored
+                .filter(|&position| position > last)
+                .unwrap_or(last + 1);
+            last = position;
+            let changed = snapshot
+                .get(&word.id)
+                .is_none_or(|saved| !same_content(saved, word))
+                || stored.is_none();
+            if !changed && stored == Some(position) {
+                continue;
+            }
+            upsert_word.execute(params![
+                word.id,
+                position,
+                word.english,
+                to_json(&word.extra)
+            ])?;
+            if !changed {
+                continue;
+            }
+            delete_senses.execute([&word.id])?;
+            delete_tags.execute([&word.id])?;
+            for (position, sense) in word.senses.iter().enumerate() {
+                insert_sense.execute(params![
+                    word.id,
+                    position as i64,
+                    sense.meaning,
+                    sense.part_of_speech,
+                    to_json(&sense.examples),
+                    to_json(&sense.extra)
+                ])?;
+            }
+            for tag in &word.tags {
+                insert_tag.execute(params![word.id, tag])?;
+            }
+        }
+        write_schedules(
+            &transaction,
+            deck.words.iter().filter(|word| {
+                snapshot
+                    .get(&word.id)
+                    .is_none_or(|saved| saved.schedule != word.schedule)
+            }),
+        )?;
+    }
+    transaction.commit()
+}
+
+/// 学習スケジュール以外の内容が同じかどうか
+fn same_content(a: &Word, b: &Word) -> bool {
+    a.english == b.english && a.senses == b.senses && a.tags == b.tags && a.extra == b.extra
+}
+
+fn write_schedules<'a>(
+    connection: &Connection,
+    words: impl IntoIterator<Item = &'a Word>,
+) -> rusqlite::Result<()> {
+    let mut upsert = connection.prepare(
+        "insert into schedules (word_id, ease_factor, interval, repetitions, due, last_grade)
+         values (?1, ?2, ?3, ?4, ?5, ?6)
+         on conflict (word_id) do update set
+             ease_factor = excluded.ease_factor,
+             interval = excluded.interval,
+             repetitions = excluded.repetitions,
+             due = excluded.due,
+             last_grade = excluded.last_grade",
+    )?;
+    let mut delete = connection.prepare("delete from schedules where word_id = ?1")?;
+    for word in words {
+        match &word.schedule {
+            Some(schedule) => upsert.execute(params![
+                word.id,
+                schedule.ease_factor,
+                schedule.interval,
+                schedule.repetitions,
+                schedule.due.to_string(),
+                schedule.last_grade.map(Grade::name)
+            ])?,
+            None => delete.execute([&word.id])?,
+        };
+    }
+    Ok(())
+}
+
+fn read_reviews(connection: &Connection) -> rusqlite::Result<Vec<Event>> {
+    let mut statement = connection.prepare(
+        "select word_id, word, timestamp, action, grade, response_time_ms from reviews order by id",
+    )?;
+    statement
+        .query_map([], |row| {
+            let mut action = json!({ "action": row.get::<_, String>(3)? });
+            if let Some(grade) = row.get::<_, Option<String>>(4)? {
+                action["grade"] = Value::String(grade);
+            }
+            Ok(Event {
+                id: row.get(0)?,
+                word: row.get(1)?,
+                timestamp: from_value(Value::String(row.get(2)?))?,
+                action: from_value(action)?,
+                response_time_ms: row.get::<_, Option<i64>>(5)?.map(|time| time.max(0) as u64),
+            })
+        })?
+        .collect()
+}
+
+fn write_reviews(connection: &Connection, events: &[Event]) -> rusqlite::Result<()> {
+    let mut insert = connection.prepare(
+        "insert into reviews (word_id, word, timestamp, action, grade, response_time_ms)
+         values (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for event in events {
+        let action = serde_json::to_value(event.action).expect("an action can be serialized");
+        insert.execute(params![
+            event.id,
+            event.word,
+            event.timestamp.to_rfc3339(),
+            action["action"].as_str(),
+            action["grade"].as_str(),
+            event.response_time_ms.map(|time| time as i64)
+        ])?;
+    }
+    Ok(())
+}
+
+fn to_json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).expect("a JSON value can be serialized")
+}
+
+fn from_json<T: DeserializeOwned>(text: &str) -> rusqlite::Result<T> {
+    serde_json::from_str(text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e)))
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> rusqlite::Result<T> {
+    serde_json::from_value(value)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::EventAction;
+    use crate::temp::TempDir;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new();
+        let file = &dir.file("words.db");
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        let mut deck = Deck::default();
+        deck.metadata.name = Some("TOEIC".to_string());
+        deck.extra.insert("owner".to_string(), json!("team"));
+        deck.words.push(Word::new("run", "走る", "I run."));
+        deck.words.push(Word::new("book", "本", "I read a book."));
+        deck.words[1].senses.push(Sense {
+            meaning: "予約する".to_string(),
+            part_of_speech: Some("verb".to_string()),
+            examples: vec!["Book a table.".to_string()],
+            extra: serde_json::from_value(json!({ "note": "formal" })).unwrap(),
+        });
+        deck.words[1].extra.insert("level".to_string(), json!(3));
+        deck.words[1].tags = vec!["toeic".to_string(), "noun".to_string()];
+        deck.words[0].grade(Grade::Good, today);
+
+        let mut storage = SqliteStorage::new(file);
+        assert!(matches!(storage.load(), Err(Error::DeckNotFound { .. })));
+        storage.save(&deck).unwrap();
+        deck.words[1].grade(Grade::Again, today);
+        storage.save_schedules(&deck.words[1..]).unwrap();
+        let event = Event::new(
+            &deck.words[1],
+            EventAction::Grade {
+                grade: Grade::Again,
+            },
+            Duration::from_millis(900),
+        );
+        storage.record(std::slice::from_ref(&event)).unwrap();
+
+        let mut reopened = SqliteStorage::new(file);
+        let loaded = reopened.load().unwrap();
+        let history = reopened.history().unwrap();
+
+        assert_eq!(loaded.metadata.name.as_deref(), Some("TOEIC"));
+        assert_eq!(loaded.extra["owner"], "team");
+        assert_eq!(loaded.words.len(), 2);
+        assert_eq!(loaded.words[1].id, deck.words[1].id);
+        assert_eq!(loaded.words[1].senses, deck.words[1].senses);
+        assert_eq!(loaded.words[1].extra["level"], 3);
+        assert_eq!(loaded.words[1].tags, deck.words[1].tags);
+        assert_eq!(loaded.words[0].schedule, deck.words[0].schedule);
+        assert_eq!(loaded.words[1].schedule, deck.words[1].schedule);
+        assert_eq!(history, vec![event]);
+    }
+
+    #[test]
+    fn test_save_keeps_concurrent_changes() {
+        let dir = TempDir::new();
+        let file = &dir.file("words.db");
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut deck = Deck::default();
+        for english in ["run", "eat", "walk"] {
+            deck.words.push(Word::new(english, "意味", ""));
+        }
+        SqliteStorage::new(file).save(&deck).unwrap();
+
+        // 単語を編集する間に、別のプロセスが学習し、単語を追加する
+        let mut editor = SqliteStorage::new(file);
+        let mut edited = editor.load().unwrap();
+        let mut study = SqliteStorage::new(file);
+        let mut studied = study.load().unwrap();
+        studied.words[0].grade(Grade::Good, today);
+        study.save_schedules(&studied.words[..1]).unwrap();
+        studied.words.push(Word::new("sleep", "眠る", ""));
+        study.save(&studied).unwrap();
+
+        edited.words[1].english = "eats".to_string();
+        edited.words.remove(2);
+        editor.save(&edited).unwrap();
+        let loaded = SqliteStorage::new(file).load().unwrap();
+
+        let english = loaded
+            .words
+            .iter()
+            .map(|word| word.english.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(english, vec!["run", "eats", "sleep"]);
+        assert_eq!(loaded.words[0].schedule, studied.words[0].schedule);
+    }
+}
@@ -0,0 +1,463 @@
+use crate::error::{Error, Result};
+use crate::scheduler::today;
+use crate::word::{Word, derive_id};
+use chrono::NaiveDate;
+use log::{info, warn};
+use rand::rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// 単語ファイルの形式のバージョン
+/// 0: 単語の配列のみ
+/// 1: メタデータと単語をまとめたオブジェクト
+pub const DECK_VERSION: u64 = 1;
+
+/// 古いバージョンの単語ファイルを 1 つ新しいバージョンに変換する関数
+/// `MIGRATIONS[n]` はバージョン n を n + 1 に変換する
+const MIGRATIONS: [fn(Value) -> Value; DECK_VERSION as usize] = [migrate_v0_to_v1];
+
+/// 単語帳
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Deck {
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+    pub words: Vec<Word>,
+    /// このバージョンの vocab が知らない項目 (書き出す際にそのまま残す)
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// 単語帳の情報
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Metadata {
+    /// 単語帳の名前
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 単語帳の説明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Deck {
+    /// 表示に使う単語帳の名前 (メタデータに名前がなければファイル名)
+    pub fn name(&self, file_path: &str) -> String {
+        self.metadata.name.clone().unwrap_or_else(|| {
+            Path::new(file_path)
+                .file_stem()
+                .map_or("vocab".to_string(), |stem| {
+                    stem.to_string_lossy().into_owned()
+                })
+        })
+    }
+}
+
+impl Metadata {
+    fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none() && self.extra.is_empty()
+    }
+}
+
+/// 単語ファイル上の単語帳 (バージョンは `version_of` で確認済みのため読み捨てる)
+#[derive(Deserialize)]
+struct DeckFile {
+    #[serde(rename = "version")]
+    _version: u64,
+    #[serde(flatten)]
+    deck: Deck,
+}
+
+#[derive(Serialize)]
+struct DeckFileRef<'a> {
+    version: u64,
+    #[serde(flatten)]
+    deck: &'a Deck,
+}
+
+/// 出題日を迎えた単語のインデックスを優先度の高い順に返す
+/// 優先度が同じ単語同士の順番はランダムになる
+pub fn due_indices(words: &[Word], today: NaiveDate) -> Vec<usize> {
+    let mut indices: Vec<usize> = words
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| word.is_due(today))
+        .map(|(i, _)| i)
+        .collect();
+
+    indices.shuffle(&mut rng());
+    indices.sort_by(|&a, &b| words[b].urgency(today).total_cmp(&words[a].urgency(today)));
+    indices
+}
+
+/// 単語ファイルを読み込み、出題に必要な項目が揃っているか確認する
+/// id のない単語には英単語から決まる id を付け、単語ファイルに書き出す
+/// (書き出した後は、英単語を書き換えても id は変わらない)
+pub fn read_deck(file_path: &str) -> Result<Deck> {
+    let mut deck = load_deck(file_path)?;
+    validate(Path::new(file_path), &deck.words)?;
+    let assigned = assign_ids(&mut deck.words);
+    if assigned > 0 {
+        // id は誰が付けても同じになるため、書き出せなくても読み込みは続ける
+        match write_deck(file_path, &deck) {
+            Ok(()) => info!("Wrote ids for {assigned} words to {file_path}"),
+            Err(e) => warn!("Failed to write ids for {assigned} words to {file_path}: {e}"),
+        }
+    }
+    Ok(deck)
+}
+
+/// id のない単語と、id が他の単語と重複している単語に、英単語から決まる id を付ける
+/// 同じ単語帳からは誰が読み込んでも同じ id になる
+/// 戻り値は id を付けた単語の数
+pub fn assign_ids(words: &mut [Word]) -> usize {
+    let mut seen = HashSet::new();
+    let missing = words
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| word.id.is_empty() || !seen.insert(word.id.clone()))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    for &i in &missing {
+        let id = (0..)
+            .map(|attempt| derive_id(&words[i].english, attempt))
+            .find(|id| !seen.contains(id))
+            .expect("some attempt gives an unused id");
+        seen.insert(id.clone());
+        words[i].id = id;
+    }
+    missing.len()
+}
+
+/// 単語ファイルを読み込み、旧形式のデータを移行する (項目の検証はしない)
+/// 古いバージョンの単語ファイルは、次に書き出す際に最新のバージョンになる
+pub fn load_deck(file_path: &str) -> Result<Deck> {
+    let path = Path::new(file_path);
+    let content = match fs::read_to_string(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(Error::DeckNotFound {
+                path: path.to_path_buf(),
+            });
+        }
+        content => content?,
+    };
+    let value: Value =
+        serde_json::from_str(&content).map_err(|e| Error::parse(path, &content, e))?;
+    let version = version_of(path, &value)?;
+
+    let mut deck = if version == DECK_VERSION {
+        // 最新のバージョンは、エラーの位置がわかるよう元の文字列から読み込む
+        serde_json::from_str::<DeckFile>(&content)
+            .map_err(|e| Error::parse(path, &content, e))?
+            .deck
+    } else {
+        info!(
+            "Reading {} as deck format version {} (it will be saved as version {})",
+            path.display(),
+            version,
+            DECK_VERSION
+        );
+        let value = MIGRATIONS[version as usize..]
+            .iter()
+            .fold(value, |value, migrate| migrate(value));
+        serde_json::from_value::<DeckFile>(value)
+            .map_err(|e| Error::parse(path, &content, e))?
+            .deck
+    };
+
+    let today = today();
+    for word in deck.words.iter_mut() {
+        word.migrate_skip(today);
+    }
+
+    Ok(deck)
+}
+
+/// 単語ファイルのバージョン (単語の配列のみの場合は 0)
+fn version_of(path: &Path, value: &Value) -> Result<u64> {
+    let version = match value {
+        Value::Array(_) => 0,
+        Value::Object(object) => {
+            object
+                .get("version")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| Error::Validation {
+                    path: path.to_path_buf(),
+                    message: "the deck has no \"version\" number".to_string(),
+                })?
+        }
+        _ => {
+            return Err(Error::Validation {
+                path: path.to_path_buf(),
+                message: "the deck must be a JSON array or object".to_string(),
+            });
+        }
+    };
+    if version > DECK_VERSION {
+        return Err(Error::UnsupportedVersion {
+            path: path.to_path_buf(),
+            version,
+            supported: DECK_VERSION,
+        });
+    }
+    Ok(version)
+}
+
+/// 単語の配列を、メタデータと単語をまとめたオブジェクトにする
+fn migrate_v0_to_v1(value: Value) -> Value {
+    serde_json::json!({ "version": 1, "words": value })
+}
+
+/// 単語帳の JSON ファイルらしいかどうか (英単語を持つオブジェクトの配列か、`version` と `words` を持つオブジェクト)
+/// JSON として読めないファイルは、誤りの位置を知らせるため単語帳とみなす
+pub fn is_deck_json(path: &Path) -> bool {
+    let Ok(content) = fs::read_to_string(path) else {
+        return true;
+    };
+    match serde_json::from_str::<Value>(&content) {
+        Ok(Value::Array(words)) => words.iter().all(|word| word.get("english").is_some()),
+        Ok(Value::Object(object)) => object.contains_key("version") && object.contains_key("words"),
+        Ok(_) => false,
+        Err(_) => true,
+    }
+}
+
+/// 出題に必要な項目が揃っているか確認する
+pub fn validate(path: &Path, words: &[Word]) -> Result<()> {
+    for (i, word) in words.iter().enumerate() {
+        if let Some(problem) = missing_field(word) {
+            return Err(Error::Validation {
+                path: path.to_path_buf(),
+                message: format!("word #{} {}", i + 1, problem),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 単語 1 つについて、出題に必要な項目のうち欠けているものを説明する
+pub fn missing_field(word: &Word) -> Option<String> {
+    if word.english.trim().is_empty() {
+        Some("has an empty \"english\" field".to_string())
+    } else if word.senses.is_empty() {
+        Some(format!(
+            "\"{}\" has neither \"japanese\" nor \"senses\"",
+            word.english
+        ))
+    } else {
+        let i = word
+            .senses
+            .iter()
+            .position(|sense| sense.meaning.trim().is_empty())?;
+        Some(if word.is_flat() {
+            format!("\"{}\" has an empty \"japanese\" field", word.english)
+        } else {
+            format!(
+                "\"{}\" has an empty \"meaning\" in senses[{i}]",
+                word.english
+            )
+        })
+    }
+}
+
+/// 単語ファイルを最新のバージョンで書き出す
+pub fn write_deck(file_path: &str, deck: &Deck) -> io::Result<()> {
+    let json = serde_json::to_vec_pretty(&DeckFileRef {
+        version: DECK_VERSION,
+        deck,
+    })?;
+    write_atomically(Path::new(file_path), &json)
+}
+
+/// 置き換える前の単語ファイルを残すパス (例: `words.json` → `words.json.bak`)
+pub fn backup_path<P: AsRef<Path>>(file_path: P) -> PathBuf {
+    append_to_file_name(file_path.as_ref(), ".bak")
+}
+
+/// 一時ファイルに書いてから置き換えることで、書き込み途中で中断されてもファイルが壊れないようにする
+/// 置き換える前のファイルは `backup_path` に残す
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    // rename で置き換えられるよう、一時ファイルは同じディレクトリに作る
+    let temp_path = append_to_file_name(path, ".tmp");
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    if path.exists() {
+        fs::copy(path, backup_path(path))?;
+    }
+    fs::rename(&temp_path, path)?;
+    sync_parent_dir(path);
+    Ok(())
+}
+
+fn append_to_file_name(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// rename した結果がディスクに書き込まれるよう、ディレクトリを同期する
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if let Err(e) = File::open(dir).and_then(|dir| dir.sync_all()) {
+        warn!("Failed to sync {}: {}", dir.display(), e);
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+
+    fn deck(words: &[&str]) -> Deck {
+        Deck {
+            words: words
+                .iter()
+                .map(|english| Word::new(english, "意味", ""))
+                .collect(),
+            ..Deck::default()
+        }
+    }
+
+    #[test]
+    fn test_backup_path() {
+        assert_eq!(
+            backup_path("decks/words.json"),
+            PathBuf::from("decks/words.json.bak")
+        );
+    }
+
+    #[test]
+    fn test_write_deck_keeps_backup() {
+        let dir = TempDir::new();
+        let file = &dir.file("words.json");
+
+        write_deck(file, &deck(&["run"])).unwrap();
+        write_deck(file, &deck(&["run", "eat"])).unwrap();
+
+        let words = read_deck(file).unwrap().words;
+        let backup = fs::read_to_string(backup_path(file)).unwrap();
+        let temp_exists = append_to_file_name(Path::new(file), ".tmp").exists();
+
+        assert_eq!(words.len(), 2);
+        assert!(backup.contains("run") && !backup.contains("eat"));
+        assert!(!temp_exists);
+    }
+
+    #[test]
+    fn test_read_deck_errors() {
+        let dir = TempDir::new();
+        let file = &dir.file("words.json");
+
+        let missing = read_deck(file);
+        fs::write(file, "[{\"english\": \"run\", \"japanese\": \"走る\",}]").unwrap();
+        let broken = read_deck(file);
+        fs::write(
+            file,
+            "[{\"english\": \" \", \"example\": \"\", \"japanese\": \"走る\"}]",
+        )
+        .unwrap();
+        let invalid = read_deck(file);
+        fs::write(file, "[{\"english\": \"run\", \"example\": \"I run.\"}]").unwrap();
+        let no_meaning = read_deck(file);
+        fs::write(file, "{\"version\": 99, \"words\": []}").unwrap();
+        let newer = read_deck(file);
+
+        assert!(matches!(missing, Err(Error::DeckNotFound { .. })));
+        assert!(matches!(broken, Err(Error::Parse { line: 1, .. })));
+        assert!(matches!(invalid, Err(Error::Validation { .. })));
+        match no_meaning {
+            Err(Error::Validation { message, .. }) => {
+                assert_eq!(message, "word #1 \"run\" has an empty \"japanese\" field")
+            }
+            result => panic!("unexpected result: {:?}", result.map(|deck| deck.words)),
+        }
+        assert!(matches!(
+            newer,
+            Err(Error::UnsupportedVersion { version: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn test_upgrade_and_preserve_unknown_fields() {
+        let dir = TempDir::new();
+        let file = &dir.file("words.json");
+
+        fs::write(
+            file,
+            r#"[{"english": "run", "example": "I run.", "japanese": "走る", "note": "irregular"}]"#,
+        )
+        .unwrap();
+        let mut deck = load_deck(file).unwrap();
+        deck.extra.insert("owner".to_string(), Value::from("team"));
+        write_deck(file, &deck).unwrap();
+        let value: Value = serde_json::from_str(&fs::read_to_string(file).unwrap()).unwrap();
+        let reloaded = load_deck(file).unwrap();
+
+        assert_eq!(value["version"], DECK_VERSION);
+        assert_eq!(value["owner"], "team");
+        assert_eq!(value["words"][0]["note"], "irregular");
+        assert_eq!(reloaded.words[0].english, "run");
+    }
+
+    #[test]
+    fn test_read_deck_assigns_ids() {
+        let dir = TempDir::new();
+        let file = &dir.file("words.json");
+
+        fs::write(
+            file,
+            r#"{"version": 1, "words": [
+                {"id": "a", "english": "run", "japanese": "走る", "example": "I run."},
+                {"id": "a", "english": "eat", "japanese": "食べる", "example": "I eat."},
+                {"english": "be", "japanese": "いる", "example": "I am here."}
+            ]}"#,
+        )
+        .unwrap();
+        let deck = read_deck(file).unwrap();
+        // 付けた id は単語ファイルに書き出し、次に読み込む際はそのまま使う
+        let saved = load_deck(file).unwrap();
+        fs::remove_file(backup_path(file)).unwrap();
+        read_deck(file).unwrap();
+        let rewritten = backup_path(file).exists();
+
+        let ids = |deck: &Deck| {
+            deck.words
+                .iter()
+                .map(|word| word.id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(&deck),
+            vec!["a".to_string(), derive_id("eat", 0), derive_id("be", 0)]
+        );
+        assert_eq!(ids(&saved), ids(&deck));
+        assert!(!rewritten);
+
+        // 英単語から決まる id が他の単語の id と重なる場合は、別の id にする
+        let mut words = vec![Word::new("Run ", "走る", ""), Word::new("run", "走る", "")];
+        words[0].id = derive_id("run", 0);
+        words[1].id = String::new();
+        assign_ids(&mut words);
+        assert_eq!(words[1].id, derive_id("run", 1));
+    }
+}
@@ -46,6 +46,31 @@ impl Dictionary {
             }
         })
     }
+
+    /// 英単語の品詞の候補を取得する
+    /// 品詞は Penn Treebank のタグの先頭 2 文字 (NN: 名詞, VB: 動詞, JJ: 形容詞, RB: 副詞 など) で表す
+    /// 熟語の場合は先頭の単語の品詞になる
+    pub fn get_parts_of_speech<S: AsRef<str>>(&self, word: S) -> HashSet<String> {
+        let word = word.as_ref();
+        let Some(sentence) = self.tokenizer.sentencize(word).next() else {
+            return HashSet::new();
+        };
+        sentence
+            .into_iter()
+            .map(|token| {
+                token
+                    .word()
+                    .tags()
+                    .iter()
+                    .map(|n| n.pos().as_str())
+                    // SENT_START などの特殊なタグは除く
+                    .filter(|pos| !pos.is_empty() && !pos.contains('_'))
+                    .map(|pos| pos.chars().take(2).collect::<String>())
+                    .collect::<HashSet<_>>()
+            })
+            .find(|parts_of_speech| !parts_of_speech.is_empty())
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -70,4 +95,14 @@ mod tests {
         // on-site
         // saving
     }
+
+    #[test]
+    fn test_get_parts_of_speech() {
+        let word = Dictionary::new();
+        assert!(word.get_parts_of_speech("child").contains("NN"));
+        assert!(word.get_parts_of_speech("eat").contains("VB"));
+        assert!(word.get_parts_of_speech("quickly").contains("RB"));
+        assert!(word.get_parts_of_speech("look after").contains("VB"));
+        assert!(word.get_parts_of_speech("").is_empty());
+    }
 }
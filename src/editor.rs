@@ -0,0 +1,279 @@
+use crate::deck::missing_field;
+use crate::error::{Error, Result};
+use crate::filter::parse_tag;
+use crate::matcher::normalize;
+use crate::temp::TempFile;
+use crate::word::Word;
+use serde_json::{Map, Value};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process::Command;
+
+/// `$VISUAL` も `$EDITOR` も設定されていない場合に使うエディタ
+const DEFAULT_EDITOR: &str = "vi";
+
+/// 編集する単語の前に置く説明
+const EDIT_HEADER: &str = "\
+# Edit the word, save and close the editor. Lines starting with # are ignored.
+# Leave the file empty to cancel.
+";
+
+/// id または英単語が `key` に一致する単語のインデックスを返す
+/// id が一致する単語があればそれだけを返し、なければ英単語を表記ゆれを揃えて比べる
+pub fn find_words(words: &[Word], key: &str) -> Vec<usize> {
+    if let Some(i) = words.iter().position(|word| word.id == key) {
+        return vec![i];
+    }
+    let key = normalize(key);
+    words
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| normalize(&word.english) == key)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// `key` に一致する単語が 1 つだけであればそのインデックスを返す
+pub fn find_word(file_path: &str, words: &[Word], key: &str) -> Result<usize> {
+    match find_words(words, key).as_slice() {
+        [i] => Ok(*i),
+        [] => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no word in {file_path} has the id or English word \"{key}\""),
+        )
+        .into()),
+        indices => {
+            let candidates = indices
+                .iter()
+                .map(|&i| format!("{} ({})", words[i].id, words[i].english))
+                .collect::<Vec<_>>();
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "\"{key}\" matches {} words in {file_path}; pass one of their ids: {}",
+                    indices.len(),
+                    candidates.join(", ")
+                ),
+            )
+            .into())
+        }
+    }
+}
+
+/// 単語を TOML にしてエディタで編集する
+/// id と学習スケジュールは編集の対象にせず、元の単語のものを引き継ぐ
+/// 書式の誤りや必要な項目の不足があれば、知らせた上で編集し直すか確認する
+/// # Returns
+/// * `Some` - 編集した単語
+/// * `None` - 変更がなかった場合や、編集を取りやめた場合
+pub fn edit_word(word: &Word) -> Result<Option<Word>> {
+    let original = format!("# id = \"{}\"\n{}", word.id, to_toml(word)?);
+    let file = TempFile::new("toml");
+    fs::write(&file.0, format!("{EDIT_HEADER}{original}"))?;
+    loop {
+        run_editor(&file.0)?;
+        let content = fs::read_to_string(&file.0)?;
+        let body = content
+            .strip_prefix(EDIT_HEADER)
+            .unwrap_or(&content)
+            .to_string();
+        if body == original || is_blank(&content) {
+            return Ok(None);
+        }
+        let problem = match toml::from_str::<Word>(&content) {
+            Ok(edited) => match missing_field(&edited) {
+                Some(problem) => format!("the word {problem}"),
+                None => match edited.tags.iter().find_map(|tag| parse_tag(tag).err()) {
+                    Some(problem) => problem,
+                    None => {
+                        let mut edited = Word {
+                            id: word.id.clone(),
+                            schedule: word.schedule.clone(),
+                            ..edited
+                        };
+                        restore_nulls(word, &mut edited);
+                        return Ok(Some(edited));
+                    }
+                },
+            },
+            Err(e) => Error::parse_toml(&file.0, &content, e).to_string(),
+        };
+        eprintln!("{problem}");
+        if !confirm("Edit again?")? {
+            return Ok(None);
+        }
+    }
+}
+
+/// 単語を id を除いて TOML で書く
+/// TOML には null がないため、値が null の項目は書かない (編集後に `restore_nulls` で戻す)
+pub fn to_toml(word: &Word) -> io::Result<String> {
+    let mut word = Word {
+        id: String::new(),
+        ..word.clone()
+    };
+    remove_nulls(&mut word.extra);
+    word.senses
+        .iter_mut()
+        .for_each(|sense| remove_nulls(&mut sense.extra));
+    toml::to_string(&word).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("\"{}\" cannot be edited as TOML: {}", word.english, e),
+        )
+    })
+}
+
+/// 値が null の項目を、入れ子になったオブジェクトや配列の中も含めて除く
+fn remove_nulls(map: &mut Map<String, Value>) {
+    map.retain(|_, value| !value.is_null());
+    map.values_mut().for_each(remove_nested_nulls);
+}
+
+fn remove_nested_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => remove_nulls(map),
+        Value::Array(values) => {
+            values.retain(|value| !value.is_null());
+            values.iter_mut().for_each(remove_nested_nulls);
+        }
+        _ => {}
+    }
+}
+
+/// TOML に書かなかった null の項目のうち、編集後も書かれていないものを元に戻す
+/// (入れ子の中の null は戻さない)
+fn restore_nulls(original: &Word, edited: &mut Word) {
+    let restore = |original: &Map<String, Value>, edited: &mut Map<String, Value>| {
+        for (key, value) in original {
+            if value.is_null() && !edited.contains_key(key) {
+                edited.insert(key.clone(), Value::Null);
+            }
+        }
+    };
+    restore(&original.extra, &mut edited.extra);
+    for (original, edited) in original.senses.iter().zip(&mut edited.senses) {
+        restore(&original.extra, &mut edited.extra);
+    }
+}
+
+/// コメントと空行しかないかどうか
+fn is_blank(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .all(|line| line.is_empty() || line.starts_with('#'))
+}
+
+/// `$VISUAL`、`$EDITOR` の順にエディタを探して起動し、閉じるまで待つ
+/// エディタの指定には引数を含めてもよい (例: `code --wait`)
+fn run_editor(path: &Path) -> io::Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot start {program}: {e}")))?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{editor} exited with {status}")));
+    }
+    Ok(())
+}
+
+/// 標準入力から 1 行読む (入力の終わりでは空文字列)
+pub fn prompt(label: &str) -> io::Result<String> {
+    print!("{label}: ");
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// y/n で確認する (何も入力しなければ y)
+fn confirm(question: &str) -> io::Result<bool> {
+    let answer = prompt(&format!("{question} [Y/n]"))?;
+    Ok(!answer.eq_ignore_ascii_case("n") && !answer.eq_ignore_ascii_case("no"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::Sense;
+
+    #[test]
+    fn test_find_words() {
+        let mut words = vec![
+            Word::new("run", "走る", ""),
+            Word::new("Run", "運営する", ""),
+            Word::new("book", "本", ""),
+        ];
+        words[2].id = "run".to_string();
+
+        assert_eq!(find_words(&words, "run"), vec![2]);
+        assert_eq!(find_words(&words, "RUN "), vec![0, 1]);
+        assert_eq!(find_words(&words, &words[1].id.clone()), vec![1]);
+        assert!(find_words(&words, "walk").is_empty());
+        assert!(find_word("words.json", &words, "RUN").is_err());
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let mut word = Word::new("book", "本", "I read a book.");
+        word.senses.push(Sense {
+            meaning: "予約する".to_string(),
+            part_of_speech: Some("verb".to_string()),
+            examples: vec!["Book a table.".to_string()],
+            extra: Default::default(),
+        });
+        word.tags = vec!["toeic".to_string()];
+        word.extra.insert("level".to_string(), serde_json::json!(3));
+
+        let toml = to_toml(&word).unwrap();
+        assert!(!toml.contains(&word.id));
+        let parsed = toml::from_str::<Word>(&format!("{EDIT_HEADER}{toml}")).unwrap();
+        assert_eq!(parsed.english, word.english);
+        assert_eq!(parsed.senses, word.senses);
+        assert_eq!(parsed.tags, word.tags);
+        assert_eq!(parsed.extra, word.extra);
+        assert!(parsed.id.is_empty());
+    }
+
+    #[test]
+    fn test_toml_null_fields() {
+        let mut word = Word::new("run", "走る", "");
+        word.extra.insert("note".to_string(), Value::Null);
+        word.extra
+            .insert("forms".to_string(), serde_json::json!(["ran", null]));
+        word.senses[0]
+            .extra
+            .insert("source".to_string(), Value::Null);
+
+        let toml = to_toml(&word).unwrap();
+        let mut parsed = toml::from_str::<Word>(&toml).unwrap();
+        assert_eq!(parsed.extra["forms"], serde_json::json!(["ran"]));
+        assert!(!parsed.extra.contains_key("note"));
+
+        parsed.extra.insert("note".to_string(), "irregular".into());
+        restore_nulls(&word, &mut parsed);
+        assert_eq!(parsed.extra["note"], "irregular");
+        assert_eq!(parsed.senses[0].extra["source"], Value::Null);
+    }
+
+    #[test]
+    fn test_toml_error_position() {
+        let content = "english = \"run\"\njapanese = \n";
+        let error = toml::from_str::<Word>(content).unwrap_err();
+        match Error::parse_toml(Path::new("word.toml"), content, error) {
+            Error::Parse { line, .. } => assert_eq!(line, 2),
+            error => panic!("unexpected error: {error}"),
+        }
+    }
+}
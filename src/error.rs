@@ -0,0 +1,207 @@
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// 単語ファイルが存在しない
+    DeckNotFound {
+        path: PathBuf,
+    },
+    /// 単語ファイルの JSON (または編集した単語の TOML) が不正
+    Parse {
+        path: PathBuf,
+        message: String,
+        /// 1 始まりの行番号 (ファイルの末尾で失敗した場合も含む)
+        line: usize,
+        /// 1 始まりの列番号 (バイト単位)
+        column: usize,
+        /// エラーが起きた行の内容
+        source_line: Option<String>,
+    },
+    /// JSON としては正しいが、単語帳として扱えない
+    Validation {
+        path: PathBuf,
+        message: String,
+    },
+    /// 取り込むファイルを単語として読み込めない
+    Import {
+        path: PathBuf,
+        message: String,
+    },
+    /// この vocab より新しいバージョンの単語ファイル
+    UnsupportedVersion {
+        path: PathBuf,
+        version: u64,
+        supported: u64,
+    },
+    /// SQLite の単語帳を読み書きできない
+    Database {
+        path: PathBuf,
+        message: String,
+    },
+    /// 音声合成エンジンを利用できない
+    Speech(String),
+    Io(io::Error),
+}
+
+impl Error {
+    pub fn parse(path: &Path, content: &str, error: serde_json::Error) -> Self {
+        let (line, column) = (error.line(), error.column());
+        // serde_json のメッセージの末尾にある位置情報は別に表示する
+        let message = error.to_string();
+        let message = message
+            .strip_suffix(&format!(" at line {line} column {column}"))
+            .unwrap_or(&message)
+            .to_string();
+        Self::Parse {
+            path: path.to_path_buf(),
+            message,
+            line,
+            column,
+            source_line: line
+                .checked_sub(1)
+                .and_then(|i| content.lines().nth(i))
+                .map(str::to_string),
+        }
+    }
+
+    /// TOML のエラーは位置をバイト単位の範囲で持つため、行と列に直す
+    pub fn parse_toml(path: &Path, content: &str, error: toml::de::Error) -> Self {
+        let offset = error.span().map_or(content.len(), |span| span.start);
+        let before = &content[..offset.min(content.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        Self::Parse {
+            path: path.to_path_buf(),
+            message: error.message().to_string(),
+            line,
+            column,
+            source_line: content.lines().nth(line - 1).map(str::to_string),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DeckNotFound { path } => {
+                writeln!(f, "{} was not found", path.display())?;
+                writeln!(
+                    f,
+                    "  hint: create it with a JSON array of words, for example:"
+                )?;
+                write!(
+                    f,
+                    r#"  [{{"english": "child", "japanese": "子供", "example": "The child is playing."}}]"#
+                )?;
+                write!(f, "\n  hint: or pass another file with --file <FILE>")
+            }
+            Self::Parse {
+                path,
+                message,
+                line,
+                column,
+                source_line,
+            } => {
+                // 読み込んだ後に変換したデータのエラーには位置がない
+                if *line == 0 {
+                    return write!(f, "failed to parse {}: {}", path.display(), message);
+                }
+                write!(
+                    f,
+                    "failed to parse {}:{}:{}: {}",
+                    path.display(),
+                    line,
+                    column,
+                    message
+                )?;
+                if let Some(source_line) = source_line {
+                    write_snippet(f, *line, *column, source_line)?;
+                }
+                Ok(())
+            }
+            Self::Validation { path, message } => {
+                write!(f, "invalid deck {}: {}", path.display(), message)
+            }
+            Self::Import { path, message } => {
+                write!(f, "cannot import {}: {}", path.display(), message)
+            }
+            Self::UnsupportedVersion {
+                path,
+                version,
+                supported,
+            } => {
+                writeln!(
+                    f,
+                    "{} uses deck format version {}, but this vocab supports up to version {}",
+                    path.display(),
+                    version,
+                    supported
+                )?;
+                write!(f, "  hint: upgrade vocab to open it")
+            }
+            Self::Database { path, message } => {
+                write!(f, "database error in {}: {}", path.display(), message)
+            }
+            Self::Speech(message) => write!(f, "speech is unavailable: {}", message),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// エラーが起きた行と、その列を指す `^` を表示する
+fn write_snippet(
+    f: &mut Formatter<'_>,
+    line: usize,
+    column: usize,
+    source_line: &str,
+) -> fmt::Result {
+    let number = line.to_string();
+    let gutter = " ".repeat(number.len());
+    // 列番号はバイト単位なので、文字の境界に合わせてから表示幅を求める
+    let mut end = column.saturating_sub(1).min(source_line.len());
+    while !source_line.is_char_boundary(end) {
+        end -= 1;
+    }
+    let indent = " ".repeat(UnicodeWidthStr::width(&source_line[..end]));
+    write!(
+        f,
+        "\n{gutter} |\n{number} | {source_line}\n{gutter} | {indent}^"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_snippet() {
+        let content = "[\n  {\"english\": \"child\" \"japanese\": \"子供\"}\n]";
+        let e = serde_json::from_str::<serde_json::Value>(content).unwrap_err();
+        let error = Error::parse(Path::new("words.json"), content, e);
+
+        assert_eq!(
+            error.to_string(),
+            "failed to parse words.json:2:23: expected `,` or `}`\n  |\n2 |   {\"english\": \"child\" \"japanese\": \"子供\"}\n  |                       ^"
+        );
+    }
+}
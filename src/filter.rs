@@ -0,0 +1,329 @@
+use crate::word::Word;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+
+/// タグの論理式で演算子として扱う記号 (タグには使えない)
+const OPERATOR_CHARS: [char; 5] = ['(', ')', '!', '&', '|'];
+
+/// 学習の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Status {
+    /// 未学習
+    New,
+    /// 学習中 (暗記済みを除く)
+    Learning,
+    /// 暗記済み
+    Memorized,
+    /// 今日出題される
+    Due,
+}
+
+/// 対象にする単語の条件 (指定した条件を全て満たす単語だけを対象にする)
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct Filter {
+    /// このタグの付いた単語だけを対象にする (繰り返し指定するといずれかのタグ)
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+    /// このタグの付いた単語を除く (繰り返し指定可)
+    #[arg(long = "exclude-tag", value_name = "TAG")]
+    pub exclude_tags: Vec<String>,
+    /// タグの論理式に合う単語だけを対象にする (例: "toeic and not (phrasal or idiom)")
+    #[arg(long = "tag-expr", value_name = "EXPR", value_parser = TagExpr::parse)]
+    pub expression: Option<TagExpr>,
+    /// 英単語・日本語訳・例文にこの文字列を含む単語だけを対象にする (大文字と小文字は区別しない)
+    #[arg(long, value_name = "TEXT")]
+    pub search: Option<String>,
+    /// この状態の単語だけを対象にする (繰り返し指定するといずれかの状態)
+    #[arg(long, value_enum)]
+    pub status: Vec<Status>,
+}
+
+impl Filter {
+    /// 単語が全ての条件を満たすかどうか
+    pub fn matches(&self, word: &Word, today: NaiveDate) -> bool {
+        (self.tags.is_empty() || self.tags.iter().any(|tag| has_tag(word, tag)))
+            && !self.exclude_tags.iter().any(|tag| has_tag(word, tag))
+            && self
+                .expression
+                .as_ref()
+                .is_none_or(|expression| expression.matches(word))
+            && self
+                .search
+                .as_deref()
+                .is_none_or(|text| contains_text(word, text))
+            && (self.status.is_empty()
+                || self
+                    .status
+                    .iter()
+                    .any(|&status| has_status(word, status, today)))
+    }
+}
+
+/// タグの論理式
+/// `and` (`&`)・`or` (`|`)・`not` (`!`) と括弧でタグを組み合わせる。演算子を省略して並べたタグは `and` でつなぐ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagExpr {
+    Tag(String),
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+impl TagExpr {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let tokens = tokenize(text);
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let expression = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(token) => Err(format!("unexpected \"{token}\" in tag expression")),
+        }
+    }
+
+    pub fn matches(&self, word: &Word) -> bool {
+        match self {
+            Self::Tag(tag) => has_tag(word, tag),
+            Self::Not(expression) => !expression.matches(word),
+            Self::And(a, b) => a.matches(word) && b.matches(word),
+            Self::Or(a, b) => a.matches(word) || b.matches(word),
+        }
+    }
+}
+
+/// タグの論理式の再帰下降構文解析
+/// or := and ("or" and)*
+/// and := not ("and"? not)*
+/// not := "not" not | "(" or ")" | タグ
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<TagExpr, String> {
+        let mut expression = self.parse_and()?;
+        while self.peek().is_some_and(is_or) {
+            self.position += 1;
+            expression = TagExpr::Or(Box::new(expression), Box::new(self.parse_and()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<TagExpr, String> {
+        let mut expression = self.parse_not()?;
+        loop {
+            match self.peek() {
+                Some(token) if is_and(token) => self.position += 1,
+                Some(token) if token == ")" || is_or(token) => break,
+                Some(_) => {}
+                None => break,
+            }
+            expression = TagExpr::And(Box::new(expression), Box::new(self.parse_not()?));
+        }
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<TagExpr, String> {
+        match self.next().as_deref() {
+            Some("not" | "!") => Ok(TagExpr::Not(Box::new(self.parse_not()?))),
+            Some("(") => {
+                let expression = self.parse_or()?;
+                match self.next().as_deref() {
+                    Some(")") => Ok(expression),
+                    _ => Err("missing \")\" in tag expression".to_string()),
+                }
+            }
+            Some(token) if is_and(token) || is_or(token) || token == ")" => Err(format!(
+                "expected a tag before \"{token}\" in tag expression"
+            )),
+            Some(tag) => Ok(TagExpr::Tag(tag.to_string())),
+            None => Err("tag expression ends where a tag is expected".to_string()),
+        }
+    }
+}
+
+fn is_and(token: &str) -> bool {
+    matches!(token, "and" | "&" | "&&")
+}
+
+fn is_or(token: &str) -> bool {
+    matches!(token, "or" | "|" | "||")
+}
+
+/// 空白と演算子の記号で区切る (`&&` と `||` は 1 つの字句にする)
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut token = c.to_string();
+        if c == '&' || c == '|' {
+            if chars.peek() == Some(&c) {
+                token.push(chars.next().expect("the next char was peeked"));
+            }
+        } else if !OPERATOR_CHARS.contains(&c) {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || OPERATOR_CHARS.contains(&c) {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// タグの論理式で使えるタグかどうか (空白や演算子の記号を含まず、演算子の単語でもない)
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && !tag
+            .chars()
+            .any(|c| c.is_whitespace() || OPERATOR_CHARS.contains(&c))
+        && !matches!(tag, "and" | "or" | "not")
+}
+
+/// 単語に付けるタグを確かめる (タグの論理式で使えないタグは受け付けない)
+pub fn parse_tag(tag: &str) -> Result<String, String> {
+    if is_valid_tag(tag) {
+        Ok(tag.to_string())
+    } else {
+        Err(format!(
+            "tag \"{tag}\" cannot be used in a tag expression (tags cannot contain spaces, parentheses, !, & or |, or be and, or, not)"
+        ))
+    }
+}
+
+/// タグは大文字と小文字を区別しない
+fn has_tag(word: &Word, tag: &str) -> bool {
+    word.tags
+        .iter()
+        .any(|word_tag| word_tag.to_lowercase() == tag.to_lowercase())
+}
+
+/// 英単語・日本語訳・例文のいずれかが `text` を含むかどうか (大文字と小文字は区別しない)
+pub fn contains_text(word: &Word, text: &str) -> bool {
+    let text = text.to_lowercase();
+    word.texts()
+        .any(|field| field.to_lowercase().contains(&text))
+}
+
+fn has_status(word: &Word, status: Status, today: NaiveDate) -> bool {
+    match (status, &word.schedule) {
+        (Status::New, schedule) => schedule.is_none(),
+        (Status::Learning, Some(schedule)) => !schedule.is_memorized(),
+        (Status::Memorized, Some(schedule)) => schedule.is_memorized(),
+        (Status::Learning | Status::Memorized, None) => false,
+        (Status::Due, _) => word.is_due(today),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::Grade;
+
+    fn word(english: &str, tags: &[&str]) -> Word {
+        let mut word = Word::new(english, "意味", "An example.");
+        word.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        word
+    }
+
+    #[test]
+    fn test_parse_tag_expr() {
+        let tag = |tag: &str| Box::new(TagExpr::Tag(tag.to_string()));
+        assert_eq!(
+            TagExpr::parse("toeic and not (phrasal | idiom)"),
+            Ok(TagExpr::And(
+                tag("toeic"),
+                Box::new(TagExpr::Not(Box::new(TagExpr::Or(
+                    tag("phrasal"),
+                    tag("idiom")
+                ))))
+            ))
+        );
+        // 演算子を省略した場合は and、and は or より先に結びつく
+        assert_eq!(
+            TagExpr::parse("a b || !c"),
+            Ok(TagExpr::Or(
+                Box::new(TagExpr::And(tag("a"), tag("b"))),
+                Box::new(TagExpr::Not(tag("c")))
+            ))
+        );
+        assert!(TagExpr::parse("toeic and").is_err());
+        assert!(TagExpr::parse("(toeic").is_err());
+        assert!(TagExpr::parse("toeic)").is_err());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let words = [
+            word("run", &["TOEIC", "verb"]),
+            word("give up", &["toeic", "phrasal"]),
+            word("book", &[]),
+        ];
+        let mut filter = Filter {
+            tags: vec!["toeic".to_string()],
+            exclude_tags: vec!["phrasal".to_string()],
+            ..Filter::default()
+        };
+        let matched = |filter: &Filter| {
+            words
+                .iter()
+                .filter(|word| filter.matches(word, today))
+                .map(|word| word.english.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matched(&filter), vec!["run"]);
+
+        filter = Filter {
+            expression: Some(TagExpr::parse("not toeic or phrasal").unwrap()),
+            ..Filter::default()
+        };
+        assert_eq!(matched(&filter), vec!["give up", "book"]);
+
+        filter = Filter {
+            search: Some("GIVE".to_string()),
+            ..Filter::default()
+        };
+        assert_eq!(matched(&filter), vec!["give up"]);
+
+        let mut learning = word("walk", &[]);
+        learning.grade(Grade::Good, today);
+        let filter = Filter {
+            status: vec![Status::Learning],
+            ..Filter::default()
+        };
+        assert!(filter.matches(&learning, today));
+        assert!(!filter.matches(&words[0], today));
+    }
+
+    #[test]
+    fn test_is_valid_tag() {
+        assert!(is_valid_tag("toeic-800"));
+        assert!(is_valid_tag("動詞"));
+        assert!(!is_valid_tag("phrasal verb"));
+        assert!(!is_valid_tag("a|b"));
+        assert!(!is_valid_tag("not"));
+        assert!(!is_valid_tag(""));
+        assert_eq!(parse_tag("toeic"), Ok("toeic".to_string()));
+        assert!(parse_tag("(toeic)").is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::scheduler::Grade;
+use crate::word::Word;
+use chrono::{DateTime, Local};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 学習履歴の 1 件分の記録
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Event {
+    /// 対象の単語の id (id を導入する前の記録にはない)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// 対象の単語 (記録した時点の英単語)
+    pub word: String,
+    /// 記録した日時
+    pub timestamp: DateTime<Local>,
+    /// 行った操作
+    #[serde(flatten)]
+    pub action: EventAction,
+    /// 単語を表示してから操作するまでの時間 (ミリ秒)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_time_ms: Option<u64>,
+}
+
+impl Event {
+    pub fn new(word: &Word, action: EventAction, response_time: Duration) -> Self {
+        Self {
+            id: Some(word.id.clone()),
+            word: word.english.clone(),
+            timestamp: Local::now(),
+            action,
+            response_time_ms: Some(response_time.as_millis() as u64),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum EventAction {
+    /// 答えを表示した
+    Reveal,
+    /// 自己評価をつけた
+    Grade { grade: Grade },
+    /// 暗記済みにした
+    MarkMemorized,
+}
+
+/// 単語帳ごとの追記専用の学習履歴 (JSON Lines 形式)
+pub struct History {
+    file: File,
+}
+
+impl History {
+    /// 単語帳と同じディレクトリにある履歴ファイルを開く (存在しない場合は作成する)
+    pub fn open(deck_path: &str) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(history_path(deck_path))?;
+        Ok(Self { file })
+    }
+
+    /// 記録を 1 件 1 行で追記する
+    /// 途中で終了しても記録が失われないよう、その場でディスクに書き出す
+    pub fn record(&mut self, events: &[Event]) -> io::Result<()> {
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event)?);
+            lines.push('\n');
+        }
+        self.file.write_all(lines.as_bytes())?;
+        self.file.sync_data()
+    }
+}
+
+/// 単語帳に対応する履歴ファイルのパス (`words.json` なら `words.history.jsonl`)
+pub fn history_path(deck_path: &str) -> PathBuf {
+    Path::new(deck_path).with_extension("history.jsonl")
+}
+
+/// 履歴ファイルを読み込む。履歴ファイルが存在しない場合は空になる
+/// 書き込み途中で壊れた行は読み飛ばす
+pub fn read_history(deck_path: &str) -> io::Result<Vec<Event>> {
+    let file = match File::open(history_path(deck_path)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut events = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(event) => events.push(event),
+            Err(e) => warn!("Skipping broken history line {}: {}", i + 1, e),
+        }
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+
+    #[test]
+    fn test_history_path() {
+        assert_eq!(
+            history_path("decks/words.json"),
+            PathBuf::from("decks/words.history.jsonl")
+        );
+    }
+
+    #[test]
+    fn test_record_and_read_history() {
+        let dir = TempDir::new();
+        let deck = &dir.file("words.json");
+
+        let run = Word::new("run", "走る", "");
+        let reveal = Event::new(&run, EventAction::Reveal, Duration::from_millis(1200));
+        let grade = Event::new(
+            &run,
+            EventAction::Grade { grade: Grade::Good },
+            Duration::from_millis(2500),
+        );
+        let mut history = History::open(deck).unwrap();
+        history.record(std::slice::from_ref(&reveal)).unwrap();
+        history.record(std::slice::from_ref(&grade)).unwrap();
+        drop(history);
+
+        // 書き込み途中で中断された行
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(history_path(deck))
+            .unwrap();
+        file.write_all(b"{\"word\":\"ru").unwrap();
+
+        let events = read_history(deck).unwrap();
+
+        assert_eq!(events, vec![reveal, grade]);
+    }
+
+    #[test]
+    fn test_event_format() {
+        let run = Word::new("run", "走る", "");
+        let event = Event::new(
+            &run,
+            EventAction::Grade { grade: Grade::Hard },
+            Duration::from_millis(800),
+        );
+        let json: serde_json::Value = serde_json::to_value(&event).unwrap();
+
+        assert_eq!(json["id"], run.id.as_str());
+        assert_eq!(json["word"], "run");
+        assert_eq!(json["action"], "grade");
+        assert_eq!(json["grade"], "hard");
+        assert_eq!(json["response_time_ms"], 800);
+    }
+}
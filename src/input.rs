@@ -0,0 +1,138 @@
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Color;
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute};
+use std::io::{self, Stdout, Write};
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// 終了シグナルを受け取ったかどうか
+static TERMINATED: AtomicBool = AtomicBool::new(false);
+
+/// 終了シグナルを確認する間隔
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// SIGINT / SIGTERM を受け取ったら、`read_event` で中断を知らせるようにする
+/// raw モードでない (入力を待っていない) 間は、そのまま終了する
+pub fn handle_termination_signals() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        if terminal::is_raw_mode_enabled().unwrap_or(false) {
+            TERMINATED.store(true, Ordering::SeqCst);
+        } else {
+            exit(130);
+        }
+    })
+}
+
+/// キー入力などのイベントを待つ
+/// Ctrl-C が押されるか終了シグナルを受け取った場合は `ErrorKind::Interrupted` のエラーを返す
+pub fn read_event() -> io::Result<Event> {
+    loop {
+        if TERMINATED.load(Ordering::SeqCst) {
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        if !event::poll(SIGNAL_POLL_INTERVAL)? {
+            continue;
+        }
+        let event = event::read()?;
+        // raw モードでは Ctrl-C がシグナルにならず、キー入力として届く
+        if let Event::Key(key_event) = &event
+            && key_event.kind == KeyEventKind::Press
+            && key_event.code == KeyCode::Char('c')
+            && key_event.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        return Ok(event);
+    }
+}
+
+/// raw モードのまま 1 行分の入力を受け付ける
+/// 入力中の文字列は `y` 行目の中央に表示する
+/// IME で確定した文字列は 1 文字ずつ届くため、そのまま末尾に追加する
+/// # Returns
+/// * `Some(String)` - Enter で確定した場合
+/// * `None` - Esc で入力をやめた場合
+pub fn read_line(stdout: &mut Stdout, y: u16) -> io::Result<Option<String>> {
+    read_line_with_replay(stdout, y, || {})
+}
+
+/// `read_line` と同じく 1 行分の入力を受け付け、Tab が押されたら `replay` を呼ぶ
+pub fn read_line_with_replay(
+    stdout: &mut Stdout,
+    y: u16,
+    mut replay: impl FnMut(),
+) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    execute!(stdout, cursor::Show)?;
+    let result = loop {
+        render_line(stdout, &line, y)?;
+        match read_event()? {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                let control = key_event.modifiers.contains(KeyModifiers::CONTROL);
+                match key_event.code {
+                    KeyCode::Enter => break Some(line),
+                    KeyCode::Esc => break None,
+                    KeyCode::Backspace => remove_last_grapheme(&mut line),
+                    KeyCode::Tab => replay(),
+                    KeyCode::Char('u') if control => line.clear(),
+                    KeyCode::Char(c) if !control => line.push(c),
+                    _ => {} // 他のキーは無視する
+                }
+            }
+            Event::Paste(text) => line.extend(text.chars().filter(|c| !c.is_control())),
+            _ => {}
+        }
+    };
+    execute!(stdout, cursor::Hide)?;
+    Ok(result)
+}
+
+/// 濁点などの結合文字も含めて、見た目上の 1 文字分を削除する
+fn remove_last_grapheme(line: &mut String) {
+    if let Some((i, _)) = line.grapheme_indices(true).next_back() {
+        line.truncate(i);
+    }
+}
+
+fn render_line(stdout: &mut Stdout, line: &str, y: u16) -> io::Result<()> {
+    let (cols, _) = terminal::size()?;
+    let prompt = "> ";
+    let width = UnicodeWidthStr::width(prompt) + UnicodeWidthStr::width(line);
+    let x = cols.saturating_sub(width as u16) / 2;
+    execute!(
+        stdout,
+        cursor::MoveTo(0, y),
+        terminal::Clear(ClearType::CurrentLine),
+        cursor::MoveTo(x, y),
+        crossterm::style::SetForegroundColor(Color::DarkGrey)
+    )?;
+    print!("{prompt}");
+    execute!(stdout, crossterm::style::ResetColor)?;
+    print!("{line}");
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_last_grapheme() {
+        let mut line = "かか\u{3099}".to_string();
+        remove_last_grapheme(&mut line);
+        assert_eq!(line, "か");
+
+        let mut line = "子供".to_string();
+        remove_last_grapheme(&mut line);
+        assert_eq!(line, "子");
+
+        let mut line = String::new();
+        remove_last_grapheme(&mut line);
+        assert_eq!(line, "");
+    }
+}
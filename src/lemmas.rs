@@ -0,0 +1,124 @@
+use crate::deck::write_atomically;
+use crate::dictionary::Dictionary;
+use crate::lint::base_form;
+use crate::progress::data_dir;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// 原形を求めたトークナイザを区別するため、キャッシュに書く vocab のバージョン
+const CACHE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// 英単語の原形のキャッシュ
+/// 全ての単語帳で共有し、利用者ごとのデータディレクトリに保存する
+/// キャッシュにない英単語があるまでトークナイザを読み込まない
+pub struct Lemmas {
+    /// キャッシュファイルのパス (データディレクトリがない場合は保存しない)
+    path: Option<PathBuf>,
+    /// 小文字にした英単語ごとの原形 (原形を求められない英単語は None)
+    lemmas: BTreeMap<String, Option<String>>,
+    dictionary: Option<Dictionary>,
+    changed: bool,
+}
+
+/// キャッシュファイルの内容
+#[derive(Deserialize, Serialize)]
+struct CacheFile {
+    version: String,
+    lemmas: BTreeMap<String, Option<String>>,
+}
+
+impl Lemmas {
+    /// `$XDG_DATA_HOME/vocab/lemmas.json` のキャッシュを開く
+    pub fn open() -> Self {
+        Self::open_in(data_dir().map(|dir| dir.join("lemmas.json")))
+    }
+
+    /// キャッシュを開く (読み込めないキャッシュや、別のバージョンの vocab が作ったキャッシュは使わない)
+    fn open_in(path: Option<PathBuf>) -> Self {
+        let lemmas = path
+            .as_ref()
+            .and_then(|path| match fs::read_to_string(path) {
+                Ok(content) => match serde_json::from_str::<CacheFile>(&content) {
+                    Ok(file) => (file.version == CACHE_VERSION).then_some(file.lemmas),
+                    Err(e) => {
+                        warn!("Ignoring the lemma cache {}: {}", path.display(), e);
+                        None
+                    }
+                },
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    warn!("Ignoring the lemma cache {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self {
+            path,
+            lemmas,
+            dictionary: None,
+            changed: false,
+        }
+    }
+
+    /// 英単語 1 語の原形 (`lint::base_form` と同じ)
+    pub fn base_form(&mut self, english: &str) -> Option<String> {
+        let key = english.trim().to_lowercase();
+        if let Some(lemma) = self.lemmas.get(&key) {
+            return lemma.clone();
+        }
+        let dictionary = self.dictionary.get_or_insert_with(|| {
+            info!("Initializing tokenizer...");
+            Dictionary::new()
+        });
+        let lemma = base_form(dictionary, english);
+        self.lemmas.insert(key, lemma.clone());
+        self.changed = true;
+        lemma
+    }
+
+    /// 新しく求めた原形があればキャッシュファイルに書き出す
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = self.path.as_ref().filter(|_| self.changed) else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = CacheFile {
+            version: CACHE_VERSION.to_string(),
+            lemmas: self.lemmas.clone(),
+        };
+        write_atomically(path, &serde_json::to_vec(&file)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempFile;
+    use serde_json::json;
+
+    #[test]
+    fn test_cached_lemmas() {
+        let file = TempFile::new("json");
+        let cache = |version: &str| {
+            json!({ "version": version, "lemmas": { "went": "go", "the": null } }).to_string()
+        };
+
+        fs::write(&file.0, cache(CACHE_VERSION)).unwrap();
+        let mut lemmas = Lemmas::open_in(Some(file.0.clone()));
+        // キャッシュにある英単語だけなら、トークナイザを読み込まない
+        assert_eq!(lemmas.base_form(" Went "), Some("go".to_string()));
+        assert_eq!(lemmas.base_form("the"), None);
+        assert!(lemmas.dictionary.is_none() && !lemmas.changed);
+
+        fs::write(&file.0, cache("0.0.0")).unwrap();
+        assert!(Lemmas::open_in(Some(file.0.clone())).lemmas.is_empty());
+        fs::write(&file.0, "{").unwrap();
+        assert!(Lemmas::open_in(Some(file.0.clone())).lemmas.is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use crate::database::is_deck_database;
+use crate::deck::is_deck_json;
+use crate::error::Result;
+use crate::storage::{self, Storage, is_database};
+use crate::word::Word;
+use log::warn;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// 同時に開いた複数の単語帳
+/// 全ての単語帳の単語を 1 つの列につなげて扱い、保存する際は単語ごとに元の単語帳に書き戻す
+pub struct Library {
+    decks: Vec<Source>,
+    /// 全ての単語帳の単語 (単語帳ごとに連続して並ぶ)
+    pub words: Vec<Word>,
+}
+
+/// 単語を読み込んだ単語帳
+pub struct Source {
+    /// 表示に使う単語帳の名前
+    pub name: String,
+    /// `Library::words` のうち、この単語帳の単語の範囲
+    pub range: Range<usize>,
+    storage: Box<dyn Storage>,
+}
+
+impl Library {
+    /// 単語ファイルを開く (ディレクトリの場合はその中の単語ファイルを全て開く)
+    pub fn open(paths: &[String]) -> Result<Self> {
+        let mut library = Self {
+            decks: Vec::new(),
+            words: Vec::new(),
+        };
+        for path in expand_paths(paths)? {
+            let mut storage = storage::open(&path);
+            let deck = storage.load()?;
+            let name = deck.name(&path);
+            let start = library.words.len();
+            library.words.extend(deck.words);
+            library.decks.push(Source {
+                name,
+                range: start..library.words.len(),
+                storage,
+            });
+        }
+        Ok(library)
+    }
+
+    pub fn decks(&self) -> &[Source] {
+        &self.decks
+    }
+
+    /// `words` の `idx` 番目の単語を読み込んだ単語帳の `decks` 中のインデックス
+    pub fn deck_of(&self, idx: usize) -> usize {
+        self.decks
+            .iter()
+            .position(|deck| deck.range.contains(&idx))
+            .expect("every word belongs to a deck")
+    }
+
+    /// `words` の `idx` 番目の単語を読み込んだ単語帳の保存先
+    pub fn storage_of(&mut self, idx: usize) -> &mut dyn Storage {
+        self.words_and_storage(idx).1
+    }
+
+    /// 全ての単語と、`idx` 番目の単語を読み込んだ単語帳の保存先を同時に借りる
+    pub fn words_and_storage(&mut self, idx: usize) -> (&[Word], &mut dyn Storage) {
+        let deck = self.deck_of(idx);
+        (&self.words, self.decks[deck].storage.as_mut())
+    }
+
+    /// `words` の `idx` 番目の単語の学習スケジュールを元の単語帳に保存する
+    pub fn save_schedule(&mut self, idx: usize) -> Result<()> {
+        let deck = self.deck_of(idx);
+        self.decks[deck]
+            .storage
+            .save_schedules(&self.words[idx..=idx])
+    }
+
+    /// 全ての単語の学習スケジュールをそれぞれの単語帳に保存する
+    pub fn save_all_schedules(&mut self) -> Result<()> {
+        for deck in &mut self.decks {
+            deck.storage
+                .save_schedules(&self.words[deck.range.clone()])?;
+        }
+        Ok(())
+    }
+}
+
+/// ディレクトリをその中の単語ファイル (JSON と SQLite) に展開する
+/// ディレクトリの中は名前順に並べ、サブディレクトリは見ない。同じファイルは 1 度だけ開く
+pub fn expand_paths(paths: &[String]) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !Path::new(path).is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut decks = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|file| file.is_file() && is_deck_file(file))
+            .map(|file| file.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        if decks.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no deck files (.json, .db, .sqlite, .sqlite3) in {path}"),
+            )
+            .into());
+        }
+        decks.sort();
+        files.extend(decks);
+    }
+    let mut seen = Vec::new();
+    files.retain(|file| {
+        let key = fs::canonicalize(file).unwrap_or_else(|_| file.into());
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+    Ok(files)
+}
+
+/// ディレクトリの中の単語ファイルかどうか
+/// 拡張子が合っていても単語帳でないファイル (`package.json` など) は、知らせた上で除く
+fn is_deck_file(path: &Path) -> bool {
+    let deck = if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+    {
+        is_deck_json(path)
+    } else if is_database(&path.to_string_lossy()) {
+        is_deck_database(path)
+    } else {
+        return false;
+    };
+    if !deck {
+        warn!("Skipping {}, which is not a deck", path.display());
+    }
+    deck
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Deck;
+    use crate::scheduler::Grade;
+    use crate::temp::TempDir;
+    use chrono::NaiveDate;
+
+    fn write_database(path: &Path, name: Option<&str>, words: &[&str]) {
+        let mut deck = Deck::default();
+        deck.metadata.name = name.map(str::to_string);
+        deck.words = words
+            .iter()
+            .map(|english| Word::new(english, "意味", ""))
+            .collect();
+        storage::open(path.to_str().unwrap()).save(&deck).unwrap();
+    }
+
+    #[test]
+    fn test_open_directory_and_save_to_origin() {
+        let temp = TempDir::new();
+        let dir = &temp.0;
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        write_database(&dir.join("b.db"), None, &["run", "walk"]);
+        write_database(&dir.join("a.db"), Some("TOEIC"), &["book"]);
+        write_database(&dir.join("nested/c.db"), None, &["skip"]);
+        fs::write(dir.join("notes.txt"), "not a deck").unwrap();
+        fs::write(dir.join("package.json"), r#"{"name": "notes"}"#).unwrap();
+        rusqlite::Connection::open(dir.join("other.db"))
+            .unwrap()
+            .execute_batch("create table notes (text text)")
+            .unwrap();
+
+        let paths = vec![
+            dir.to_string_lossy().into_owned(),
+            dir.join("a.db").to_string_lossy().into_owned(),
+        ];
+        let mut library = Library::open(&paths).unwrap();
+        let names = library
+            .decks()
+            .iter()
+            .map(|deck| deck.name.as_str())
+            .collect::<Vec<_>>();
+        let english = library
+            .words
+            .iter()
+            .map(|word| word.english.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["TOEIC", "b"]);
+        assert_eq!(english, vec!["book", "run", "walk"]);
+        assert_eq!(library.deck_of(0), 0);
+        assert_eq!(library.deck_of(2), 1);
+
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        library.words[2].grade(Grade::Good, today);
+        library.save_schedule(2).unwrap();
+        let walk = storage::open(dir.join("b.db").to_str().unwrap())
+            .load()
+            .unwrap()
+            .words
+            .remove(1);
+        let book = storage::open(dir.join("a.db").to_str().unwrap())
+            .load()
+            .unwrap()
+            .words
+            .remove(0);
+
+        assert_eq!(walk.schedule, library.words[2].schedule);
+        assert_eq!(book.schedule, None);
+    }
+
+    #[test]
+    fn test_missing_directory_decks() {
+        let dir = TempDir::new();
+        let result = expand_paths(&[dir.0.to_string_lossy().into_owned()]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,426 @@
+use crate::dictionary::Dictionary;
+use crate::filter::is_valid_tag;
+use crate::matcher::normalize;
+use crate::styled_text::{Style, StyledText, print_styled_text};
+use crate::stylist::has_inflection;
+use crate::word::Word;
+use clap::ValueEnum;
+use crossterm::style::Color;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Stdout};
+use unicode_width::UnicodeWidthStr;
+
+/// 検査結果の出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// 人が読むための形式
+    Human,
+    /// CI などで扱うための JSON
+    Json,
+}
+
+/// 検査項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// 項目が空
+    EmptyField,
+    /// 同じ英単語が複数ある
+    Duplicate,
+    /// 原形が同じ英単語が複数ある
+    SharedLemma,
+    /// 例文に英単語の活用形が含まれていない
+    MissingInflection,
+    /// 前後に空白がある
+    Whitespace,
+    /// 端末に収まらないほど長い
+    LongLine,
+    /// タグの論理式で指定できないタグ
+    InvalidTag,
+}
+
+impl Rule {
+    fn name(self) -> &'static str {
+        match self {
+            Rule::EmptyField => "empty-field",
+            Rule::Duplicate => "duplicate",
+            Rule::SharedLemma => "shared-lemma",
+            Rule::MissingInflection => "missing-inflection",
+            Rule::Whitespace => "whitespace",
+            Rule::LongLine => "long-line",
+            Rule::InvalidTag => "invalid-tag",
+        }
+    }
+}
+
+/// 単語帳の問題点
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    /// 単語ファイル中の位置 (1 始まり)
+    pub index: usize,
+    pub english: String,
+    pub rule: Rule,
+    /// 問題のある項目の単語ファイル上の名前 (例: `japanese`, `senses[1].examples[0]`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(index: usize, word: &Word, rule: Rule, field: Option<String>, message: String) -> Self {
+        Self {
+            index: index + 1,
+            english: word.english.clone(),
+            rule,
+            field,
+            message,
+        }
+    }
+}
+
+/// 単語帳を検査し、見つかった問題点を単語ファイル中の順に返す
+/// # Arguments
+/// * `max_width` - 1 つの項目に許容する最大の表示幅
+pub fn lint(dictionary: &Dictionary, words: &[Word], max_width: usize) -> Vec<Problem> {
+    let mut problems = words
+        .iter()
+        .enumerate()
+        .flat_map(|(i, word)| check_word(i, word, max_width))
+        .collect::<Vec<_>>();
+
+    problems.extend(find_duplicates(words, |english| {
+        base_form(dictionary, english)
+    }));
+
+    for (i, word) in words.iter().enumerate() {
+        let english = word.english.trim();
+        if english.is_empty() {
+            continue;
+        }
+        for (field, example) in examples(word) {
+            let example = example.trim();
+            if !example.is_empty() && has_inflection(dictionary, example, english) == Some(false) {
+                problems.push(Problem::new(
+                    i,
+                    word,
+                    Rule::MissingInflection,
+                    Some(field),
+                    format!("example does not contain any form of \"{english}\""),
+                ));
+            }
+        }
+    }
+
+    problems.sort_by_key(|problem| problem.index);
+    problems
+}
+
+/// 英単語 1 語の原形 (複数の語や記号を含む場合は求めない)
+pub fn base_form(dictionary: &Dictionary, english: &str) -> Option<String> {
+    let english = english.trim();
+    if english.is_empty() || !english.chars().all(|c| c.is_alphabetic() || c == '-') {
+        return None;
+    }
+    dictionary.get_base_form(english.to_lowercase())
+}
+
+/// 単語 1 つで判断できる問題点 (空の項目、前後の空白、長すぎる項目) を探す
+fn check_word(index: usize, word: &Word, max_width: usize) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if word.examples().next().is_none() {
+        problems.push(Problem::new(
+            index,
+            word,
+            Rule::EmptyField,
+            Some("example".to_string()),
+            "word has no examples".to_string(),
+        ));
+    }
+
+    let mut fields = vec![("english".to_string(), word.english.as_str())];
+    fields.extend(meanings(word));
+    fields.extend(examples(word));
+    for (field, value) in fields {
+        let problem = if value.trim().is_empty() {
+            Some((Rule::EmptyField, format!("{field} is empty")))
+        } else if value.trim() != value {
+            Some((
+                Rule::Whitespace,
+                format!("{field} has leading or trailing whitespace"),
+            ))
+        } else if UnicodeWidthStr::width(value) > max_width {
+            Some((
+                Rule::LongLine,
+                format!(
+                    "{field} is {} columns wide (max {max_width})",
+                    UnicodeWidthStr::width(value)
+                ),
+            ))
+        } else {
+            None
+        };
+        if let Some((rule, message)) = problem {
+            problems.push(Problem::new(index, word, rule, Some(field), message));
+        }
+    }
+    for (i, tag) in word.tags.iter().enumerate() {
+        if !is_valid_tag(tag) {
+            problems.push(Problem::new(
+                index,
+                word,
+                Rule::InvalidTag,
+                Some(format!("tags[{i}]")),
+                format!("tag \"{tag}\" cannot be used in a tag expression"),
+            ));
+        }
+    }
+    problems
+}
+
+/// 日本語訳と、その単語ファイル上の項目名
+fn meanings(word: &Word) -> Vec<(String, &str)> {
+    if word.is_flat() {
+        return word
+            .senses
+            .iter()
+            .map(|sense| ("japanese".to_string(), sense.meaning.as_str()))
+            .collect();
+    }
+    word.senses
+        .iter()
+        .enumerate()
+        .map(|(i, sense)| (format!("senses[{i}].meaning"), sense.meaning.as_str()))
+        .collect()
+}
+
+/// 例文と、その単語ファイル上の項目名
+fn examples(word: &Word) -> Vec<(String, &str)> {
+    if word.is_flat() {
+        return word
+            .senses
+            .iter()
+            .flat_map(|sense| &sense.examples)
+            .map(|example| ("example".to_string(), example.as_str()))
+            .collect();
+    }
+    word.senses
+        .iter()
+        .enumerate()
+        .flat_map(|(i, sense)| {
+            sense
+                .examples
+                .iter()
+                .enumerate()
+                .map(move |(j, example)| (format!("senses[{i}].examples[{j}]"), example.as_str()))
+        })
+        .collect()
+}
+
+/// 同じ英単語、または原形が同じ英単語を探す
+/// 2 つ目以降の単語を、最初に出てきた単語の重複として報告する
+/// # Arguments
+/// * `base_form` - 英単語の原形を求める関数
+fn find_duplicates<F>(words: &[Word], base_form: F) -> Vec<Problem>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut seen_words = HashMap::new();
+    let mut seen_lemmas: HashMap<String, usize> = HashMap::new();
+    let mut problems = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        let english = normalize(&word.english);
+        if english.is_empty() {
+            continue;
+        }
+        if let Some(&first) = seen_words.get(&english) {
+            problems.push(Problem::new(
+                i,
+                word,
+                Rule::Duplicate,
+                Some("english".to_string()),
+                format!("same word as #{}", first + 1),
+            ));
+            continue;
+        }
+        seen_words.insert(english, i);
+
+        if let Some(lemma) = base_form(&word.english) {
+            if let Some(&first) = seen_lemmas.get(&lemma) {
+                problems.push(Problem::new(
+                    i,
+                    word,
+                    Rule::SharedLemma,
+                    Some("english".to_string()),
+                    format!(
+                        "shares the base form \"{lemma}\" with #{} \"{}\"",
+                        first + 1,
+                        words[first].english
+                    ),
+                ));
+            } else {
+                seen_lemmas.insert(lemma, i);
+            }
+        }
+    }
+    problems
+}
+
+/// 検査結果を出力する
+pub fn print_report(
+    stdout: &mut Stdout,
+    file_path: &str,
+    words: usize,
+    problems: &[Problem],
+    format: Format,
+) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            #[derive(Serialize)]
+            struct Report<'a> {
+                file: &'a str,
+                words: usize,
+                problems: &'a [Problem],
+            }
+            let report = Report {
+                file: file_path,
+                words,
+                problems,
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        Format::Human => {
+            for problem in problems {
+                let field = problem
+                    .field
+                    .as_ref()
+                    .map(|field| format!(".{field}"))
+                    .unwrap_or_default();
+                println!(
+                    "{file_path}: #{}{field} \"{}\": {} [{}]",
+                    problem.index,
+                    problem.english,
+                    problem.message,
+                    problem.rule.name()
+                );
+            }
+            let summary = if problems.is_empty() {
+                StyledText::new(
+                    &format!("✔ {words} words, no problems found"),
+                    Color::Green,
+                    Style::Bold,
+                )
+            } else {
+                StyledText::new(
+                    &format!("✘ {words} words, {} problems found", problems.len()),
+                    Color::Red,
+                    Style::Bold,
+                )
+            };
+            print_styled_text(stdout, &summary);
+            println!();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::Sense;
+
+    fn word(english: &str, japanese: &str, example: &str) -> Word {
+        Word::new(english, japanese, example)
+    }
+
+    fn rules(problems: &[Problem]) -> Vec<(usize, Rule)> {
+        problems
+            .iter()
+            .map(|problem| (problem.index, problem.rule))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_word() {
+        let problems = check_word(0, &word("run ", "", "I run every morning."), 10);
+        assert_eq!(
+            rules(&problems),
+            vec![
+                (1, Rule::Whitespace),
+                (1, Rule::EmptyField),
+                (1, Rule::LongLine)
+            ]
+        );
+        assert_eq!(problems[1].field.as_deref(), Some("japanese"));
+
+        assert!(check_word(0, &word("run", "走る", "I run."), 10).is_empty());
+
+        let mut book = word("book", "本", "I read a book.");
+        book.senses.push(Sense {
+            meaning: "予約する".to_string(),
+            part_of_speech: Some("verb".to_string()),
+            examples: vec![" Book a table.".to_string()],
+            extra: Default::default(),
+        });
+        let problems = check_word(0, &book, 80);
+        assert_eq!(rules(&problems), vec![(1, Rule::Whitespace)]);
+        assert_eq!(problems[0].field.as_deref(), Some("senses[1].examples[0]"));
+
+        book.tags = vec!["toeic".to_string(), "phrasal verb".to_string()];
+        let problems = check_word(0, &book, 80);
+        assert_eq!(
+            rules(&problems),
+            vec![(1, Rule::Whitespace), (1, Rule::InvalidTag)]
+        );
+        assert_eq!(problems[1].field.as_deref(), Some("tags[1]"));
+    }
+
+    #[test]
+    fn test_missing_inflection() {
+        let dictionary = Dictionary::new();
+        let mut go = word("go", "行く", "She went home.");
+        go.senses.push(Sense {
+            meaning: "進む".to_string(),
+            part_of_speech: None,
+            examples: vec![
+                "Things are going well.".to_string(),
+                "Let's walk.".to_string(),
+            ],
+            extra: Default::default(),
+        });
+        let words = vec![
+            go,
+            word("run", "走る", "I walk every day."),
+            word("eat", "食べる", "I eat rice."),
+        ];
+
+        let problems = lint(&dictionary, &words, 80);
+        assert_eq!(
+            rules(&problems),
+            vec![(1, Rule::MissingInflection), (2, Rule::MissingInflection)]
+        );
+        assert_eq!(problems[0].field.as_deref(), Some("senses[1].examples[1]"));
+        assert_eq!(problems[1].field.as_deref(), Some("example"));
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let words = vec![
+            word("run", "走る", ""),
+            word("eat", "食べる", ""),
+            word("Run", "走る", ""),
+            word("ran", "走った", ""),
+        ];
+        let base_form = |word: &str| match word {
+            "run" | "Run" | "ran" => Some("run".to_string()),
+            _ => None,
+        };
+
+        let problems = find_duplicates(&words, base_form);
+        assert_eq!(
+            rules(&problems),
+            vec![(3, Rule::Duplicate), (4, Rule::SharedLemma)]
+        );
+        assert_eq!(problems[0].message, "same word as #1");
+    }
+}
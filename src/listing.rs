@@ -0,0 +1,287 @@
+use crate::word::Word;
+use clap::ValueEnum;
+use csv::Writer;
+use serde_json::{Map, Value, json};
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use unicode_width::UnicodeWidthStr;
+
+/// 列を指定しない場合に表示する列
+pub const DEFAULT_COLUMNS: [Column; 5] = [
+    Column::English,
+    Column::Japanese,
+    Column::State,
+    Column::Due,
+    Column::Tags,
+];
+
+/// 一覧の列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Id,
+    English,
+    /// 全ての意味の日本語訳
+    Japanese,
+    /// 最初の例文
+    Example,
+    /// 学習の状態 (new, learning, memorized)
+    State,
+    /// 次回の出題日
+    Due,
+    /// 復習間隔 (日)
+    Interval,
+    /// 易しさ係数
+    Ease,
+    Tags,
+    /// 単語帳の名前
+    Deck,
+}
+
+impl Column {
+    fn name(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::English => "english",
+            Column::Japanese => "japanese",
+            Column::Example => "example",
+            Column::State => "state",
+            Column::Due => "due",
+            Column::Interval => "interval",
+            Column::Ease => "ease",
+            Column::Tags => "tags",
+            Column::Deck => "deck",
+        }
+    }
+}
+
+/// 一覧の出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    /// 端末で読むための表
+    Table,
+    /// 列の名前をキーにしたオブジェクトの配列
+    Json,
+    /// 見出し付きの CSV
+    Csv,
+}
+
+/// 一覧の 1 行
+pub struct Entry<'a> {
+    pub word: &'a Word,
+    /// 単語を読み込んだ単語帳の名前
+    pub deck: &'a str,
+}
+
+impl Entry<'_> {
+    /// 列の値 (該当する値がなければ null)
+    fn value(&self, column: Column) -> Value {
+        let schedule = self.word.schedule.as_ref();
+        match column {
+            Column::Id => json!(self.word.id),
+            Column::English => json!(self.word.english),
+            Column::Japanese => json!(self.word.meaning()),
+            Column::Example => self
+                .word
+                .examples()
+                .next()
+                .map_or(Value::Null, |e| json!(e)),
+            Column::State => json!(match schedule {
+                None => "new",
+                Some(schedule) if schedule.is_memorized() => "memorized",
+                Some(_) => "learning",
+            }),
+            Column::Due => schedule.map_or(Value::Null, |s| json!(s.due.to_string())),
+            Column::Interval => schedule.map_or(Value::Null, |s| json!(s.interval)),
+            Column::Ease => schedule.map_or(Value::Null, |s| {
+                json!((s.ease_factor * 100.0).round() / 100.0)
+            }),
+            Column::Tags => json!(self.word.tags),
+            Column::Deck => json!(self.deck),
+        }
+    }
+}
+
+/// 列の値で並べ替える (値のない単語は昇順でも降順でも最後)
+/// 同じ値の単語同士は元の順のまま
+pub fn sort_entries(entries: &mut [Entry], column: Column, reverse: bool) {
+    entries.sort_by(|a, b| {
+        let (a, b) = (a.value(column), b.value(column));
+        match (a.is_null(), b.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) if reverse => compare(&b, &a),
+            (false, false) => compare(&a, &b),
+        }
+    });
+}
+
+/// 数値は数値として、それ以外は表示する文字列を大文字と小文字を区別せずに比べる
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        _ => text(a).to_lowercase().cmp(&text(b).to_lowercase()),
+    }
+}
+
+/// 表や CSV に書く文字列 (タグは空白区切り、値がなければ空。表では空の値を `-` と書く)
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values.iter().map(text).collect::<Vec<_>>().join(" "),
+        value => value.to_string(),
+    }
+}
+
+pub fn write_list<W: Write>(
+    mut writer: W,
+    entries: &[Entry],
+    columns: &[Column],
+    format: ListFormat,
+) -> io::Result<()> {
+    match format {
+        ListFormat::Table => {
+            let header = columns
+                .iter()
+                .map(|column| column.name().to_uppercase())
+                .collect::<Vec<_>>();
+            let rows = entries
+                .iter()
+                .map(|entry| {
+                    columns
+                        .iter()
+                        .map(|&column| match text(&entry.value(column)) {
+                            cell if cell.is_empty() => "-".to_string(),
+                            cell => cell,
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let widths = (0..columns.len())
+                .map(|i| {
+                    std::iter::once(&header)
+                        .chain(&rows)
+                        .map(|row| UnicodeWidthStr::width(row[i].as_str()))
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect::<Vec<_>>();
+            for row in std::iter::once(&header).chain(&rows) {
+                let mut line = String::new();
+                for (i, cell) in row.iter().enumerate() {
+                    if i + 1 == row.len() {
+                        line.push_str(cell);
+                    } else {
+                        let padding = widths[i] - UnicodeWidthStr::width(cell.as_str());
+                        line.push_str(&format!("{cell}{}  ", " ".repeat(padding)));
+                    }
+                }
+                writeln!(writer, "{}", line.trim_end())?;
+            }
+        }
+        ListFormat::Json => {
+            let objects = entries
+                .iter()
+                .map(|entry| {
+                    columns
+                        .iter()
+                        .map(|&column| (column.name().to_string(), entry.value(column)))
+                        .collect::<Map<_, _>>()
+                })
+                .collect::<Vec<_>>();
+            serde_json::to_writer_pretty(&mut writer, &objects)?;
+            writeln!(writer)?;
+        }
+        ListFormat::Csv => {
+            let mut writer = Writer::from_writer(writer);
+            writer.write_record(columns.iter().map(|column| column.name()))?;
+            for entry in entries {
+                writer.write_record(columns.iter().map(|&column| text(&entry.value(column))))?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::{Grade, Schedule};
+    use chrono::NaiveDate;
+
+    fn words() -> Vec<Word> {
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut run = Word::new("run", "走る", "I run.");
+        run.tags = vec!["verb".to_string(), "toeic".to_string()];
+        run.grade(Grade::Good, today);
+        let mut book = Word::new("Book", "本", "");
+        book.schedule = Some(Schedule::memorized(today));
+        vec![run, book, Word::new("apple", "りんご", "")]
+    }
+
+    fn english(entries: &[Entry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| entry.word.english.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let words = words();
+        let mut entries = words
+            .iter()
+            .map(|word| Entry {
+                word,
+                deck: "TOEIC",
+            })
+            .collect::<Vec<_>>();
+
+        sort_entries(&mut entries, Column::English, false);
+        assert_eq!(english(&entries), vec!["apple", "Book", "run"]);
+        // 未学習の単語には出題日がないため、降順でも最後になる
+        sort_entries(&mut entries, Column::Due, true);
+        assert_eq!(english(&entries), vec!["Book", "run", "apple"]);
+        sort_entries(&mut entries, Column::Interval, false);
+        assert_eq!(english(&entries), vec!["run", "Book", "apple"]);
+    }
+
+    #[test]
+    fn test_write_list() {
+        let words = words();
+        let entries = words
+            .iter()
+            .map(|word| Entry {
+                word,
+                deck: "TOEIC",
+            })
+            .collect::<Vec<_>>();
+        let columns = [Column::English, Column::State, Column::Tags, Column::Due];
+        let write = |format| {
+            let mut buffer = Vec::new();
+            write_list(&mut buffer, &entries[..2], &columns, format).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+
+        let due = words[0].schedule.as_ref().unwrap().due;
+        assert_eq!(
+            write(ListFormat::Table),
+            format!(
+                "ENGLISH  STATE      TAGS        DUE\n\
+                 run      learning   verb toeic  {due}\n\
+                 Book     memorized  -           2025-03-02\n"
+            )
+        );
+        assert_eq!(
+            write(ListFormat::Csv),
+            format!(
+                "english,state,tags,due\nrun,learning,verb toeic,{due}\nBook,memorized,,2025-03-02\n"
+            )
+        );
+        let json: Value = serde_json::from_str(&write(ListFormat::Json)).unwrap();
+        assert_eq!(json[0]["tags"], json!(["verb", "toeic"]));
+        assert_eq!(json[1]["state"], "memorized");
+    }
+}
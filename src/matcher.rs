@@ -0,0 +1,439 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// 表記が異なるだけで同じ意味になる漢字 (異体字 → 常用の字体)
+const KANJI_VARIANTS: [(char, char); 12] = [
+    ('髙', '高'),
+    ('﨑', '崎'),
+    ('邊', '辺'),
+    ('邉', '辺'),
+    ('澤', '沢'),
+    ('齋', '斎'),
+    ('齊', '斉'),
+    ('廣', '広'),
+    ('國', '国'),
+    ('學', '学'),
+    ('體', '体'),
+    ('爲', '為'),
+];
+
+/// 1 つの欄に複数の答えを書く際の区切り文字
+const ALTERNATIVE_SEPARATORS: [char; 7] = ['、', ',', '，', '/', '／', ';', '；'];
+
+/// 入力された答えの判定結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// 表記ゆれを除いて一致した
+    Correct,
+    /// 活用形で答えたが、原形が一致した
+    Inflected,
+    /// 多少の打ち間違いはあるが正解とみなせる
+    Close,
+    /// 不正解
+    Wrong,
+}
+
+/// 判定結果と、最も近かった答え
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Judgement {
+    pub verdict: Verdict,
+    /// 入力と比較した答え (区切り文字で複数書かれている場合はそのうちの 1 つ)
+    pub expected: String,
+    /// 活用形で答えた場合の原形
+    pub base_form: Option<String>,
+}
+
+/// 入力された答えを判定する
+/// 期待する答えが区切り文字で複数書かれている場合は、最も近いものと比較する
+pub fn judge(typed: &str, expected: &str) -> Judgement {
+    let typed = normalize(typed);
+    let best = alternatives(expected)
+        .map(|alternative| {
+            let distance = levenshtein(&typed, &normalize(alternative));
+            (alternative, distance)
+        })
+        .min_by_key(|&(_, distance)| distance);
+
+    let Some((alternative, distance)) = best else {
+        return Judgement {
+            verdict: Verdict::Wrong,
+            expected: expected.to_string(),
+            base_form: None,
+        };
+    };
+    let verdict = if distance == 0 {
+        Verdict::Correct
+    } else if !typed.is_empty() && distance <= allowed_typos(alternative) {
+        Verdict::Close
+    } else {
+        Verdict::Wrong
+    };
+    Judgement {
+        verdict,
+        expected: alternative.to_string(),
+        base_form: None,
+    }
+}
+
+/// 例文の空欄に入る語形をそのまま答えたかどうかを判定する
+/// 前後と語の間の空白の違いだけを無視し、表記ゆれや打ち間違いは許容しない
+pub fn judge_exact(typed: &str, expected: &str) -> Judgement {
+    let typed = typed.split_whitespace().collect::<Vec<_>>();
+    let verdict = if typed == expected.split_whitespace().collect::<Vec<_>>() {
+        Verdict::Correct
+    } else {
+        Verdict::Wrong
+    };
+    Judgement {
+        verdict,
+        expected: expected.to_string(),
+        base_form: None,
+    }
+}
+
+/// 英単語の答えを判定する
+/// 表記が一致しなくても、原形が答えと同じであれば活用形で答えたとみなす
+/// # Arguments
+/// * `base_form` - 英単語の原形を求める関数
+pub fn judge_english<F>(typed: &str, expected: &str, base_form: F) -> Judgement
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let judgement = judge(typed, expected);
+    if judgement.verdict == Verdict::Correct {
+        return judgement;
+    }
+
+    let Some(typed_base) = base_form(typed.trim()) else {
+        return judgement;
+    };
+    alternatives(expected)
+        .find_map(|alternative| {
+            let expected_base = base_form(alternative)?;
+            (normalize(&expected_base) == normalize(&typed_base)).then(|| Judgement {
+                verdict: Verdict::Inflected,
+                expected: alternative.to_string(),
+                base_form: Some(expected_base),
+            })
+        })
+        .unwrap_or(judgement)
+}
+
+/// 区切り文字で書かれた答えの候補
+pub fn alternatives(expected: &str) -> impl Iterator<Item = &str> {
+    expected
+        .split(ALTERNATIVE_SEPARATORS)
+        .map(str::trim)
+        .filter(|alternative| !normalize(alternative).is_empty())
+}
+
+/// 答えの長さに応じて許容する打ち間違いの数
+fn allowed_typos(expected: &str) -> usize {
+    (normalize(expected).chars().count() / 4).min(3)
+}
+
+/// 比較のために表記ゆれを揃える
+/// 全角・半角、カタカナ・ひらがな、異体字、大文字・小文字、空白の違いを無視する
+pub fn normalize(text: &str) -> String {
+    text.nfkc()
+        .filter(|&c| c != '~' && c != '〜')
+        .map(fold_char)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 1 文字単位で表記ゆれを揃える (NFKC 正規化済みの文字を想定)
+fn fold_char(c: char) -> char {
+    let c = match c {
+        // カタカナをひらがなに揃える
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    };
+    let c = KANJI_VARIANTS
+        .iter()
+        .find(|&&(variant, _)| variant == c)
+        .map_or(c, |&(_, standard)| standard);
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// 2 つの文字が表記ゆれを除いて同じかどうか
+fn same_char(a: char, b: char) -> bool {
+    let fold = |c: char| c.nfkc().next().map(fold_char);
+    fold(a) == fold(b)
+}
+
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 入力と答えの差分の 1 文字 (または 1 語) 分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diff<T> {
+    /// 一致している (入力, 答え)
+    Equal(T, T),
+    /// 入力が答えと異なる (入力, 答え)
+    Substitute(T, T),
+    /// 入力に余分な文字がある
+    Extra(T),
+    /// 入力に文字が足りない
+    Missing(T),
+}
+
+/// 入力を答えに書き換えるための差分を文字単位で求める
+pub fn diff(typed: &str, expected: &str) -> Vec<Diff<char>> {
+    let a = typed.chars().collect::<Vec<_>>();
+    let b = expected.chars().collect::<Vec<_>>();
+    align(&a, &b, same_char)
+}
+
+/// 入力を答えに書き換えるための差分を単語単位で求める
+/// 記号だけの語は比較に使わない
+pub fn diff_tokens<'a>(typed: &'a str, expected: &'a str) -> Vec<Diff<&'a str>> {
+    let a = tokens(typed);
+    let b = tokens(expected);
+    align(&a, &b, |a, b| normalize_token(a) == normalize_token(b))
+}
+
+/// 書き取った文を単語単位で判定する
+/// 打ち間違いが許容範囲に収まる単語しか異ならない場合は `Close` とする
+pub fn judge_dictation(typed: &str, expected: &str) -> Judgement {
+    let diffs = diff_tokens(typed, expected);
+    let verdict = if diffs.iter().all(|diff| matches!(diff, Diff::Equal(..))) {
+        Verdict::Correct
+    } else if diffs.iter().all(|diff| match *diff {
+        Diff::Equal(..) => true,
+        Diff::Substitute(typed, expected) => judge(typed, expected).verdict == Verdict::Close,
+        Diff::Extra(_) | Diff::Missing(_) => false,
+    }) {
+        Verdict::Close
+    } else {
+        Verdict::Wrong
+    };
+    Judgement {
+        verdict,
+        expected: expected.to_string(),
+        base_form: None,
+    }
+}
+
+/// 空白で区切った語のうち、英数字を含むもの
+fn tokens(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .filter(|token| token.chars().any(char::is_alphanumeric))
+        .collect()
+}
+
+/// 語の前後の記号を除いて表記ゆれを揃える
+fn normalize_token(token: &str) -> String {
+    normalize(token.trim_matches(|c: char| !c.is_alphanumeric()))
+}
+
+/// 編集距離が最小になるように `a` と `b` の要素を対応付ける
+fn align<T: Copy>(a: &[T], b: &[T], same: impl Fn(T, T) -> bool) -> Vec<Diff<T>> {
+    // table[i][j]: a[i..] を b[j..] に書き換えるための編集距離
+    let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..=a.len()).rev() {
+        for j in (0..=b.len()).rev() {
+            table[i][j] = if i == a.len() {
+                b.len() - j
+            } else if j == b.len() {
+                a.len() - i
+            } else {
+                let cost = usize::from(!same(a[i], b[j]));
+                (table[i + 1][j + 1] + cost)
+                    .min(table[i + 1][j] + 1)
+                    .min(table[i][j + 1] + 1)
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diffs = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() {
+            let same = same(a[i], b[j]);
+            if table[i][j] == table[i + 1][j + 1] + usize::from(!same) {
+                diffs.push(if same {
+                    Diff::Equal(a[i], b[j])
+                } else {
+                    Diff::Substitute(a[i], b[j])
+                });
+                i += 1;
+                j += 1;
+                continue;
+            }
+        }
+        if i < a.len() && (j == b.len() || table[i][j] == table[i + 1][j] + 1) {
+            diffs.push(Diff::Extra(a[i]));
+            i += 1;
+        } else {
+            diffs.push(Diff::Missing(b[j]));
+            j += 1;
+        }
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("ＡＢＣ　 def "), "abc def");
+        assert_eq!(normalize("カタカナ"), "かたかな");
+        assert_eq!(normalize("ｶﾀｶﾅ"), "かたかな");
+        assert_eq!(normalize("〜である"), "である");
+        assert_eq!(normalize("髙い"), "高い");
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("子供", "子ども"), 2);
+    }
+
+    #[test]
+    fn test_judge() {
+        assert_eq!(judge("である", "〜である").verdict, Verdict::Correct);
+        assert_eq!(judge("モツ", "もつ").verdict, Verdict::Correct);
+        assert_eq!(judge("Child", "child").verdict, Verdict::Correct);
+        assert_eq!(judge("dangerus", "dangerous").verdict, Verdict::Close);
+        assert_eq!(judge("run", "ran").verdict, Verdict::Wrong);
+        assert_eq!(judge("", "child").verdict, Verdict::Wrong);
+    }
+
+    #[test]
+    fn test_judge_alternatives() {
+        let judgement = judge("所有する", "持つ、所有する");
+        assert_eq!(judgement.verdict, Verdict::Correct);
+        assert_eq!(judgement.expected, "所有する");
+    }
+
+    #[test]
+    fn test_judge_exact() {
+        assert_eq!(
+            judge_exact(" gone  away ", "gone away").verdict,
+            Verdict::Correct
+        );
+        // 活用形の誤りや打ち間違いは正解にしない
+        assert_eq!(judge_exact("goes", "gone").verdict, Verdict::Wrong);
+        assert_eq!(judge_exact("gonne", "gone").verdict, Verdict::Wrong);
+        assert_eq!(judge_exact("", "gone").verdict, Verdict::Wrong);
+    }
+
+    #[test]
+    fn test_judge_english() {
+        let base_form = |word: &str| match word {
+            "ran" | "run" | "running" => Some("run".to_string()),
+            "children" | "child" => Some("child".to_string()),
+            "eat" => Some("eat".to_string()),
+            _ => None,
+        };
+
+        let judgement = judge_english("ran", "run", base_form);
+        assert_eq!(judgement.verdict, Verdict::Inflected);
+        assert_eq!(judgement.base_form.as_deref(), Some("run"));
+
+        let judgement = judge_english("children", "child", base_form);
+        assert_eq!(judgement.verdict, Verdict::Inflected);
+
+        assert_eq!(
+            judge_english("run", "run", base_form).verdict,
+            Verdict::Correct
+        );
+        assert_eq!(
+            judge_english("eat", "run", base_form).verdict,
+            Verdict::Wrong
+        );
+        assert_eq!(
+            judge_english("rnu", "run", base_form).verdict,
+            Verdict::Wrong
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            diff("ct", "cat"),
+            vec![
+                Diff::Equal('c', 'c'),
+                Diff::Missing('a'),
+                Diff::Equal('t', 't')
+            ]
+        );
+        assert_eq!(
+            diff("caat", "cat"),
+            vec![
+                Diff::Equal('c', 'c'),
+                Diff::Equal('a', 'a'),
+                Diff::Extra('a'),
+                Diff::Equal('t', 't')
+            ]
+        );
+        assert_eq!(
+            diff("bat", "cat"),
+            vec![
+                Diff::Substitute('b', 'c'),
+                Diff::Equal('a', 'a'),
+                Diff::Equal('t', 't')
+            ]
+        );
+        assert_eq!(
+            diff("ｶﾀ", "かた"),
+            vec![Diff::Equal('ｶ', 'か'), Diff::Equal('ﾀ', 'た')]
+        );
+    }
+
+    #[test]
+    fn test_diff_tokens() {
+        assert_eq!(
+            diff_tokens("the dog eat", "The dog ate it."),
+            vec![
+                Diff::Equal("the", "The"),
+                Diff::Equal("dog", "dog"),
+                Diff::Substitute("eat", "ate"),
+                Diff::Missing("it."),
+            ]
+        );
+        assert_eq!(
+            diff_tokens("Hello, world", "Hello - world!"),
+            vec![
+                Diff::Equal("Hello,", "Hello"),
+                Diff::Equal("world", "world!")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_judge_dictation() {
+        let expected = "I have a dangerous dog.";
+        assert_eq!(
+            judge_dictation("i have a dangerous dog", expected).verdict,
+            Verdict::Correct
+        );
+        assert_eq!(
+            judge_dictation("I have a dangerus dog", expected).verdict,
+            Verdict::Close
+        );
+        assert_eq!(
+            judge_dictation("I have dangerous dog", expected).verdict,
+            Verdict::Wrong
+        );
+        assert_eq!(judge_dictation("", expected).verdict, Verdict::Wrong);
+    }
+}
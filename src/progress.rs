@@ -0,0 +1,356 @@
+use crate::deck::write_atomically;
+use crate::error::{Error, Result};
+use crate::matcher::normalize;
+use crate::scheduler::Schedule;
+use crate::word::{Word, derive_id, fnv1a};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 単語帳とは別に、利用者ごとに保存する学習の進捗
+/// 単語帳は共有されることを想定し、進捗は書き込まない
+#[derive(Debug)]
+pub struct Progress {
+    /// 進捗ファイルのパス
+    path: PathBuf,
+    /// 単語帳のパス (どの単語帳の進捗かを示すためだけに使う)
+    deck: String,
+    /// 単語の id ごとの学習スケジュール
+    schedules: BTreeMap<String, Schedule>,
+}
+
+/// 進捗ファイルの形式のバージョン
+/// 0 は単語の id を導入する前の形式で、英単語のハッシュ (`legacy_key`) をキーにしていた
+const PROGRESS_VERSION: u32 = 1;
+
+/// 進捗ファイルの内容
+#[derive(Deserialize, Serialize)]
+struct ProgressFile {
+    #[serde(default)]
+    version: u32,
+    deck: String,
+    #[serde(default)]
+    schedules: BTreeMap<String, Schedule>,
+}
+
+impl Progress {
+    /// 単語帳に対応する進捗を読み込む
+    /// 進捗ファイルがまだなければ、単語帳に書かれている学習スケジュール (旧形式の `skip` を含む) を取り込んで作る
+    /// 単語には id が付いている必要がある (`deck::read_deck` を参照)
+    pub fn open(deck_path: &str, words: &[Word]) -> Result<Self> {
+        let data_dir = data_dir().ok_or_else(|| {
+            io::Error::other("cannot find the data directory (set XDG_DATA_HOME or HOME)")
+        })?;
+        Self::open_in(&data_dir, deck_path, words)
+    }
+
+    fn open_in(data_dir: &Path, deck_path: &str, words: &[Word]) -> Result<Self> {
+        let path = progress_path(data_dir, deck_path);
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                let file: ProgressFile =
+                    serde_json::from_str(&content).map_err(|e| Error::parse(&path, &content, e))?;
+                let mut progress = Self {
+                    path,
+                    deck: file.deck,
+                    schedules: file.schedules,
+                };
+                if file.version < PROGRESS_VERSION {
+                    progress.rekey_legacy(words);
+                    info!(
+                        "Upgraded {} to progress format version {}",
+                        progress.path.display(),
+                        PROGRESS_VERSION
+                    );
+                    progress.save()?;
+                }
+                Ok(progress)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let mut progress = Self {
+                    path,
+                    deck: deck_path.to_string(),
+                    schedules: BTreeMap::new(),
+                };
+                words.iter().for_each(|word| progress.update(word));
+                info!(
+                    "Imported the progress of {} words from {} into {}",
+                    progress.schedules.len(),
+                    deck_path,
+                    progress.path.display()
+                );
+                progress.save()?;
+                Ok(progress)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 英単語のハッシュをキーにした旧形式の進捗を、単語の id をキーにしたものに置き換える
+    /// 単語帳にない英単語の進捗は捨てる
+    fn rekey_legacy(&mut self, words: &[Word]) {
+        let mut legacy = std::mem::take(&mut self.schedules);
+        for word in words {
+            if let Some(schedule) = legacy.remove(&legacy_key(word)) {
+                self.schedules.insert(word.id.clone(), schedule);
+            }
+        }
+        if !legacy.is_empty() {
+            warn!(
+                "Dropped the progress of {} words in {} that are no longer in {}",
+                legacy.len(),
+                self.path.display(),
+                self.deck
+            );
+        }
+    }
+
+    /// 保存されている学習スケジュールを単語に反映する (進捗のない単語は未学習になる)
+    /// 英単語から決まる id の進捗は、その英単語の単語に別の id が付いていれば付け替える
+    /// それ以外の単語帳にない id の進捗は捨てずに残し、単語の id が変わった可能性があるため id を知らせる
+    pub fn apply(&mut self, words: &mut [Word]) -> io::Result<()> {
+        let rekeyed = self.rekey_derived(words);
+        if rekeyed > 0 {
+            info!(
+                "Moved the progress of {} words in {} to their ids in {}",
+                rekeyed,
+                self.path.display(),
+                self.deck
+            );
+            self.save()?;
+        }
+        for word in words.iter_mut() {
+            word.schedule = self.schedules.get(&word.id).cloned();
+        }
+        let unknown = self.unknown_ids(words);
+        if !unknown.is_empty() {
+            warn!(
+                "{} has progress for word ids that are not in {}: {}; to keep the progress of a word whose id was changed, set its id back",
+                self.path.display(),
+                self.deck,
+                unknown.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// 進捗のない単語に、その英単語から決まる id (`deck::assign_ids` が付ける id) の進捗があれば付け替える
+    /// 単語ファイルに id を書き出す前に記録した進捗を、後から付いた id に引き継ぐため
+    /// 戻り値は付け替えた単語の数
+    fn rekey_derived(&mut self, words: &[Word]) -> usize {
+        let ids = words
+            .iter()
+            .map(|word| word.id.as_str())
+            .collect::<HashSet<_>>();
+        let mut rekeyed = 0;
+        for word in words {
+            let derived = derive_id(&word.english, 0);
+            if self.schedules.contains_key(&word.id) || ids.contains(derived.as_str()) {
+                continue;
+            }
+            if let Some(schedule) = self.schedules.remove(&derived) {
+                self.schedules.insert(word.id.clone(), schedule);
+                rekeyed += 1;
+            }
+        }
+        rekeyed
+    }
+
+    /// 進捗のうち、単語帳のどの単語の id でもないもの
+    fn unknown_ids(&self, words: &[Word]) -> Vec<&str> {
+        let ids = words
+            .iter()
+            .map(|word| word.id.as_str())
+            .collect::<HashSet<_>>();
+        self.schedules
+            .keys()
+            .map(String::as_str)
+            .filter(|id| !ids.contains(id))
+            .collect()
+    }
+
+    /// 単語の学習スケジュールを進捗に反映する (保存は `save` で行う)
+    pub fn update(&mut self, word: &Word) {
+        match &word.schedule {
+            Some(schedule) => self.schedules.insert(word.id.clone(), schedule.clone()),
+            None => self.schedules.remove(&word.id),
+        };
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = ProgressFile {
+            version: PROGRESS_VERSION,
+            deck: self.deck.clone(),
+            schedules: self.schedules.clone(),
+        };
+        write_atomically(&self.path, &serde_json::to_vec_pretty(&file)?)
+    }
+}
+
+/// 単語の id を導入する前に、進捗を単語と対応付けていたキー (表記ゆれを揃えた英単語のハッシュ)
+fn legacy_key(word: &Word) -> String {
+    format!("{:016x}", fnv1a(normalize(&word.english).as_bytes()))
+}
+
+/// 利用者ごとのデータを保存するディレクトリ (`$XDG_DATA_HOME/vocab` または `~/.local/share/vocab`)
+pub fn data_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(base.join("vocab"))
+}
+
+/// 単語帳ごとの進捗ファイルのパス
+/// 同じ名前の単語帳を区別するため、単語帳の絶対パスのハッシュをファイル名に含める
+fn progress_path(data_dir: &Path, deck_path: &str) -> PathBuf {
+    let deck = Path::new(deck_path);
+    let absolute = fs::canonicalize(deck).unwrap_or_else(|_| deck.to_path_buf());
+    let stem = deck
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let hash = fnv1a(absolute.to_string_lossy().as_bytes());
+    data_dir
+        .join("progress")
+        .join(format!("{stem}-{hash:016x}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{load_deck, read_deck, write_deck};
+    use crate::scheduler::Grade;
+    use crate::temp::TempDir;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_legacy_key() {
+        let key = legacy_key(&Word::new("Run", "走る", ""));
+        assert_eq!(key, legacy_key(&Word::new(" run ", "走っている", "")));
+        assert_ne!(key, legacy_key(&Word::new("ran", "走った", "")));
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+    }
+
+    #[test]
+    fn test_import_and_reopen() {
+        let temp = TempDir::new();
+        let data_dir = &temp.0;
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut words = vec![Word::new("run", "走る", ""), Word::new("eat", "食べる", "")];
+        words[0].skip = true;
+        words[0].migrate_skip(today);
+
+        // 初回は単語帳の進捗を取り込む
+        let mut progress = Progress::open_in(data_dir, "words.json", &words).unwrap();
+        words[1].grade(Grade::Good, today);
+        progress.update(&words[1]);
+        progress.save().unwrap();
+
+        // 英単語を書き換えても id が同じなら進捗は引き継がれる
+        let mut reloaded = words.clone();
+        reloaded[0].english = "sprint".to_string();
+        reloaded.iter_mut().for_each(|word| word.schedule = None);
+        Progress::open_in(data_dir, "words.json", &[])
+            .unwrap()
+            .apply(&mut reloaded)
+            .unwrap();
+
+        assert!(reloaded[0].schedule.as_ref().unwrap().is_memorized());
+        assert_eq!(reloaded[1].schedule, words[1].schedule);
+        // id が変わった単語の進捗は残し、単語帳にない id として知らせる
+        assert!(progress.unknown_ids(&reloaded).is_empty());
+        let id = std::mem::replace(&mut reloaded[1].id, "b81d05e6c2a9".to_string());
+        assert_eq!(progress.unknown_ids(&reloaded), vec![id.as_str()]);
+    }
+
+    #[test]
+    fn test_rename_keeps_progress() {
+        let temp = TempDir::new();
+        let file = &temp.file("words.json");
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        fs::write(
+            file,
+            r#"[{"english": "run", "japanese": "走る", "example": "I run."}]"#,
+        )
+        .unwrap();
+
+        // 初めて読み込んだ際に付けた id で進捗を記録する
+        let mut words = read_deck(file).unwrap().words;
+        let mut progress = Progress::open_in(&temp.0, file, &words).unwrap();
+        words[0].grade(Grade::Good, today);
+        progress.update(&words[0]);
+        progress.save().unwrap();
+
+        // 単語ファイルの英単語を書き換えても、書き出した id で進捗が引き継がれる
+        let mut deck = load_deck(file).unwrap();
+        deck.words[0].english = "sprint".to_string();
+        write_deck(file, &deck).unwrap();
+        let mut renamed = read_deck(file).unwrap().words;
+        Progress::open_in(&temp.0, file, &renamed)
+            .unwrap()
+            .apply(&mut renamed)
+            .unwrap();
+
+        assert_eq!(renamed[0].id, derive_id("run", 0));
+        assert_eq!(renamed[0].schedule, words[0].schedule);
+    }
+
+    #[test]
+    fn test_rekey_derived_ids() {
+        let temp = TempDir::new();
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut run = Word::new("run", "走る", "");
+        run.id = derive_id("run", 0);
+        run.grade(Grade::Good, today);
+        let mut progress = Progress::open_in(&temp.0, "words.json", &[run.clone()]).unwrap();
+
+        // 英単語から決まる id の進捗は、後から別の id が付いた同じ英単語に付け替える
+        let mut words = vec![Word::new("run", "走る", ""), Word::new("eat", "食べる", "")];
+        progress.apply(&mut words).unwrap();
+        let reopened = Progress::open_in(&temp.0, "words.json", &[]).unwrap();
+
+        assert_eq!(words[0].schedule, run.schedule);
+        assert!(words[1].schedule.is_none());
+        assert!(reopened.unknown_ids(&words).is_empty());
+    }
+
+    #[test]
+    fn test_upgrade_legacy_keys() {
+        let temp = TempDir::new();
+        let data_dir = &temp.0;
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut run = Word::new("run", "走る", "");
+        run.grade(Grade::Good, today);
+
+        let path = progress_path(data_dir, "words.json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let legacy = serde_json::json!({
+            "deck": "words.json",
+            "schedules": { legacy_key(&run): run.schedule },
+        });
+        fs::write(&path, legacy.to_string()).unwrap();
+
+        let mut reloaded = vec![Word {
+            schedule: None,
+            ..run.clone()
+        }];
+        Progress::open_in(data_dir, "words.json", &reloaded)
+            .unwrap()
+            .apply(&mut reloaded)
+            .unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        assert_eq!(reloaded[0].schedule, run.schedule);
+        assert_eq!(saved["version"], PROGRESS_VERSION);
+        assert!(saved["schedules"][&run.id].is_object());
+    }
+}
@@ -0,0 +1,719 @@
+use crate::choices::pick_distractors;
+use crate::dictionary::Dictionary;
+use crate::history::{Event as HistoryEvent, EventAction};
+use crate::input::{read_event, read_line, read_line_with_replay};
+use crate::matcher::{
+    Diff, Judgement, Verdict, diff, diff_tokens, judge, judge_dictation, judge_english, judge_exact,
+};
+use crate::scheduler::{Grade, today};
+use crate::screen::{print_centered, print_instructions, print_question};
+use crate::speaker::Speaker;
+use crate::storage::Storage;
+use crate::styled_text::{Style, StyledText, print_styled_text};
+use crate::stylist::{cloze_example, style_example};
+use crate::word::Word;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use crossterm::style::Color;
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute};
+use log::info;
+use rand::rng;
+use rand::seq::SliceRandom;
+use std::collections::HashSet;
+use std::io::{self, Stdout};
+use std::time::Instant;
+
+const GRADE_INSTRUCTIONS: &str =
+    "(q)uit, (m)ark memorized, (1) again, (2) hard, (3/ret) good, (4) easy";
+
+/// 出題形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Mode {
+    /// 答えを表示して自己評価する
+    Flashcard,
+    /// 答えを入力する
+    Type,
+    /// 例文の空欄に入る活用形を入力する
+    Cloze,
+    /// 4 つの選択肢から日本語訳を選ぶ
+    MultipleChoice,
+    /// 読み上げられた英単語 (または例文) を書き取る
+    Dictation,
+}
+
+/// 書き取りで読み上げる内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Dictation {
+    /// 英単語
+    Word,
+    /// 例文 (例文がない場合は英単語)
+    Example,
+}
+
+/// 答えを入力する場合の出題の向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Direction {
+    /// 英語を見て日本語を答える
+    EnJa,
+    /// 日本語を見て英語を答える
+    JaEn,
+}
+
+pub enum Action {
+    /// 答えを表示する
+    Next,
+    /// 自己評価をつけて次へ進む
+    Grade(Grade),
+    /// 暗記済みとして、しばらく出題しないようにする
+    MarkMemorized,
+    /// 終了
+    Quit,
+}
+
+/// # Arguments
+/// * `revealed` - 答えを表示済みかどうか (表示済みの場合のみ自己評価を受け付ける)
+pub fn wait_for_action(revealed: bool) -> io::Result<Action> {
+    loop {
+        if let Event::Key(key_event) = read_event()? {
+            if key_event.kind != KeyEventKind::Press {
+                continue;
+            }
+            match (key_event.code, revealed) {
+                (KeyCode::Enter, _) => return Ok(Action::Next),
+                (KeyCode::Char('1'), true) => return Ok(Action::Grade(Grade::Again)),
+                (KeyCode::Char('2'), true) => return Ok(Action::Grade(Grade::Hard)),
+                (KeyCode::Char('3'), true) => return Ok(Action::Grade(Grade::Good)),
+                (KeyCode::Char('4'), true) => return Ok(Action::Grade(Grade::Easy)),
+                (KeyCode::Char('m'), _) => return Ok(Action::MarkMemorized),
+                (KeyCode::Char('q'), _) => return Ok(Action::Quit),
+                _ => {} // 他のキーは無視する
+            }
+        }
+    }
+}
+
+pub struct Quiz<'a> {
+    dictionary: &'a Dictionary,
+    mode: Mode,
+    direction: Direction,
+    /// 穴埋めの空欄に頭文字と文字数を表示するかどうか
+    cloze_hint: bool,
+    /// 単語ごとの品詞の候補 (選択問題の場合のみ)
+    parts_of_speech: Vec<HashSet<String>>,
+    /// 書き取りで読み上げる内容
+    dictation: Dictation,
+    /// 出題する例文を決めるための、学習を始めた日
+    today: NaiveDate,
+}
+
+impl<'a> Quiz<'a> {
+    pub fn new(
+        dictionary: &'a Dictionary,
+        words: &[Word],
+        mode: Mode,
+        direction: Direction,
+        cloze_hint: bool,
+        dictation: Dictation,
+    ) -> Self {
+        let parts_of_speech = if mode == Mode::MultipleChoice {
+            info!("Tagging parts of speech...");
+            words
+                .iter()
+                .map(|word| dictionary.get_parts_of_speech(&word.english))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Self {
+            dictionary,
+            mode,
+            direction,
+            cloze_hint,
+            parts_of_speech,
+            dictation,
+            today: today(),
+        }
+    }
+
+    /// 単語を読み上げて出題し、自己評価などの操作を返す
+    /// 答えを表示した時点で学習履歴に記録する
+    /// # Arguments
+    /// * `idx` - 出題する単語の `words` 中のインデックス
+    pub fn ask(
+        &self,
+        stdout: &mut Stdout,
+        words: &[Word],
+        idx: usize,
+        speaker: &mut Speaker,
+        storage: &mut dyn Storage,
+        shown_at: Instant,
+    ) -> io::Result<Action> {
+        let word = &words[idx];
+        if self.mode == Mode::Dictation {
+            return self.ask_dictation(stdout, word, speaker, storage, shown_at);
+        }
+        speaker.speak(word.english.as_str());
+        match self.mode {
+            Mode::Flashcard => self.ask_flashcard(stdout, word, storage, shown_at),
+            Mode::Type => self.ask_typed(stdout, word, self.direction, storage, shown_at),
+            Mode::Cloze => self.ask_cloze(stdout, word, storage, shown_at),
+            Mode::MultipleChoice => self.ask_choice(stdout, words, idx, storage, shown_at),
+            Mode::Dictation => unreachable!("dictation is handled above"),
+        }
+    }
+
+    fn ask_flashcard(
+        &self,
+        stdout: &mut Stdout,
+        word: &Word,
+        storage: &mut dyn Storage,
+        shown_at: Instant,
+    ) -> io::Result<Action> {
+        let (cols, rows) = terminal::size()?;
+        print_instructions(
+            stdout,
+            cols,
+            rows,
+            "(q)uit, (m)ark memorized, (ret) show answer",
+        )?;
+        let example = word.example_on(self.today);
+        let (_, y) = print_question(
+            stdout,
+            self.dictionary,
+            cols,
+            rows,
+            word.english.as_str(),
+            example,
+        );
+
+        let action = wait_for_action(false)?;
+        if !matches!(action, Action::Next) {
+            return Ok(action);
+        }
+        record_reveal(storage, word, shown_at)?;
+
+        self.print_senses(stdout, cols, y + 2, word, example);
+        print_instructions(stdout, cols, rows, GRADE_INSTRUCTIONS)?;
+        Ok(match wait_for_action(true)? {
+            Action::Next => Action::Grade(Grade::Good),
+            action => action,
+        })
+    }
+
+    fn ask_typed(
+        &self,
+        stdout: &mut Stdout,
+        word: &Word,
+        direction: Direction,
+        storage: &mut dyn Storage,
+        shown_at: Instant,
+    ) -> io::Result<Action> {
+        let (cols, rows) = terminal::size()?;
+        print_instructions(stdout, cols, rows, "(esc) give up, (ret) answer")?;
+        let (y, expected) = match direction {
+            Direction::EnJa => {
+                let (_, y) = print_question(
+                    stdout,
+                    self.dictionary,
+                    cols,
+                    rows,
+                    word.english.as_str(),
+                    word.example_on(self.today),
+                );
+                (y, word.meaning())
+            }
+            Direction::JaEn => {
+                let y = (rows / 2).saturating_sub(2);
+                print_centered(
+                    stdout,
+                    cols,
+                    y,
+                    &[StyledText::new(&word.meaning(), Color::Yellow, Style::Bold)],
+                );
+                (y + 1, word.english.clone())
+            }
+        };
+
+        let typed = read_line(stdout, y + 2)?.unwrap_or_default();
+        record_reveal(storage, word, shown_at)?;
+        let judgement = match direction {
+            Direction::EnJa => judge(&typed, &expected),
+            Direction::JaEn => judge_english(&typed, &expected, |word| self.base_form(word)),
+        };
+
+        if direction == Direction::JaEn {
+            // 例文で答えの単語を見せる
+            let example = style_example(
+                self.dictionary,
+                format!("({})", word.example_on(self.today)).as_str(),
+                &word.english,
+            );
+            print_centered(stdout, cols, y, &example);
+        }
+        print_judgement(stdout, cols, y + 2, &typed, &judgement)?;
+
+        wait_for_grade(stdout, cols, rows, grade_of(judgement.verdict))
+    }
+
+    fn ask_cloze(
+        &self,
+        stdout: &mut Stdout,
+        word: &Word,
+        storage: &mut dyn Storage,
+        shown_at: Instant,
+    ) -> io::Result<Action> {
+        let cloze = cloze_example(
+            self.dictionary,
+            format!("({})", word.example_on(self.today)).as_str(),
+            &word.english,
+            self.cloze_hint,
+        );
+        // 例文に空欄を作れない場合は、日本語から英単語を答えてもらう
+        if cloze.answers.is_empty() {
+            return self.ask_typed(stdout, word, Direction::JaEn, storage, shown_at);
+        }
+
+        let (cols, rows) = terminal::size()?;
+        print_instructions(stdout, cols, rows, "(esc) give up, (ret) answer")?;
+        let y = (rows / 2).saturating_sub(2);
+        print_centered(
+            stdout,
+            cols,
+            y,
+            &[StyledText::new(&word.meaning(), Color::Yellow, Style::Bold)],
+        );
+        print_centered(stdout, cols, y + 1, &cloze.styled);
+
+        let typed = read_line(stdout, y + 3)?.unwrap_or_default();
+        record_reveal(storage, word, shown_at)?;
+        // 空欄が複数ある場合は、空白で区切って順に答える
+        let judgement = judge_exact(&typed, &cloze.answers.join(" "));
+
+        let example = style_example(
+            self.dictionary,
+            format!("({})", word.example_on(self.today)).as_str(),
+            &word.english,
+        );
+        execute!(
+            stdout,
+            cursor::MoveTo(0, y + 1),
+            terminal::Clear(ClearType::CurrentLine)
+        )?;
+        print_centered(stdout, cols, y + 1, &example);
+        print_judgement(stdout, cols, y + 3, &typed, &judgement)?;
+
+        wait_for_grade(stdout, cols, rows, grade_of(judgement.verdict))
+    }
+
+    fn ask_choice(
+        &self,
+        stdout: &mut Stdout,
+        words: &[Word],
+        idx: usize,
+        storage: &mut dyn Storage,
+        shown_at: Instant,
+    ) -> io::Result<Action> {
+        let word = &words[idx];
+        let mut rng = rng();
+        let mut options = pick_distractors(words, idx, &self.parts_of_speech, &mut rng);
+        options.push(idx);
+        options.shuffle(&mut rng);
+        let options = options
+            .into_iter()
+            .map(|i| (words[i].meaning(), i == idx))
+            .collect::<Vec<_>>();
+
+        let (cols, rows) = terminal::size()?;
+        print_instructions(
+            stdout,
+            cols,
+            rows,
+            "(q)uit, (m)ark memorized, (↑↓) select, (1-4/ret) choose",
+        )?;
+        let (_, y) = print_question(
+            stdout,
+            self.dictionary,
+            cols,
+            rows,
+            word.english.as_str(),
+            word.example_on(self.today),
+        );
+
+        let mut selected = 0;
+        let chosen = loop {
+            print_options(stdout, cols, y + 2, &options, selected, false)?;
+            let Event::Key(key_event) = read_event()? else {
+                continue;
+            };
+            if key_event.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key_event.code {
+                KeyCode::Up => selected = (selected + options.len() - 1) % options.len(),
+                KeyCode::Down => selected = (selected + 1) % options.len(),
+                KeyCode::Enter => break selected,
+                KeyCode::Char(c) => match c.to_digit(10) {
+                    Some(n) if (1..=options.len()).contains(&(n as usize)) => {
+                        break n as usize - 1;
+                    }
+                    _ if c == 'm' => return Ok(Action::MarkMemorized),
+                    _ if c == 'q' => return Ok(Action::Quit),
+                    _ => {}
+                },
+                _ => {} // 他のキーは無視する
+            }
+        };
+        record_reveal(storage, word, shown_at)?;
+
+        let correct = options[chosen].1;
+        print_options(stdout, cols, y + 2, &options, chosen, true)?;
+        let verdict = if correct {
+            StyledText::new("✔ correct", Color::Green, Style::Bold)
+        } else {
+            StyledText::new("✘ wrong", Color::Red, Style::Bold)
+        };
+        print_centered(stdout, cols, y + 3 + options.len() as u16, &[verdict]);
+
+        let grade = if correct { Grade::Good } else { Grade::Again };
+        wait_for_grade(stdout, cols, rows, grade)
+    }
+
+    fn ask_dictation(
+        &self,
+        stdout: &mut Stdout,
+        word: &Word,
+        speaker: &mut Speaker,
+        storage: &mut dyn Storage,
+        shown_at: Instant,
+    ) -> io::Result<Action> {
+        let text = match self.dictation {
+            Dictation::Example if word.examples().next().is_some() => word.example_on(self.today),
+            _ => word.english.as_str(),
+        };
+        speaker.speak(text);
+
+        let (cols, rows) = terminal::size()?;
+        print_instructions(
+            stdout,
+            cols,
+            rows,
+            "(tab) replay, (esc) give up, (ret) answer",
+        )?;
+        let y = rows / 2;
+        print_centered(
+            stdout,
+            cols,
+            y,
+            &[StyledText::new(
+                "🔊 Type what you hear",
+                Color::DarkGrey,
+                Style::Plain,
+            )],
+        );
+
+        let typed =
+            read_line_with_replay(stdout, y + 2, || speaker.speak(text))?.unwrap_or_default();
+        record_reveal(storage, word, shown_at)?;
+        let judgement = judge_dictation(&typed, text);
+
+        print_dictation(stdout, cols, y + 2, &typed, &judgement)?;
+        print_centered(
+            stdout,
+            cols,
+            y + 6,
+            &[StyledText::new(&word.meaning(), Color::Reset, Style::Plain)],
+        );
+
+        wait_for_grade(stdout, cols, rows, grade_of(judgement.verdict))
+    }
+
+    /// 全ての意味 (品詞があれば品詞も) と、出題した例文以外の例文を `y` 行目から順に表示する
+    fn print_senses(
+        &self,
+        stdout: &mut Stdout,
+        cols: u16,
+        y: u16,
+        word: &Word,
+        shown_example: &str,
+    ) {
+        let mut lines = word
+            .senses
+            .iter()
+            .map(|sense| {
+                let mut line = Vec::new();
+                if let Some(part_of_speech) = &sense.part_of_speech {
+                    line.push(StyledText::new(
+                        &format!("[{part_of_speech}] "),
+                        Color::DarkGrey,
+                        Style::Plain,
+                    ));
+                }
+                line.push(StyledText::new(&sense.meaning, Color::Reset, Style::Plain));
+                line
+            })
+            .collect::<Vec<_>>();
+        lines.extend(
+            word.examples()
+                .filter(|&example| example != shown_example)
+                .map(|example| {
+                    style_example(self.dictionary, &format!("({example})"), &word.english)
+                }),
+        );
+        for (i, line) in lines.iter().enumerate() {
+            print_centered(stdout, cols, y + i as u16, line);
+        }
+    }
+
+    /// 英単語 1 語の原形を求める (複数の語や記号を含む場合は求めない)
+    fn base_form(&self, word: &str) -> Option<String> {
+        if word.is_empty() || !word.chars().all(|c| c.is_alphabetic() || c == '-') {
+            return None;
+        }
+        self.dictionary.get_base_form(word.to_lowercase())
+    }
+}
+
+fn record_reveal(storage: &mut dyn Storage, word: &Word, shown_at: Instant) -> io::Result<()> {
+    storage
+        .record(&[HistoryEvent::new(
+            word,
+            EventAction::Reveal,
+            shown_at.elapsed(),
+        )])
+        .map_err(io::Error::other)
+}
+
+/// 入力した答えの判定結果に対応する自己評価
+fn grade_of(verdict: Verdict) -> Grade {
+    match verdict {
+        Verdict::Correct | Verdict::Inflected => Grade::Good,
+        Verdict::Close => Grade::Hard,
+        Verdict::Wrong => Grade::Again,
+    }
+}
+
+/// 判定結果から決めた自己評価で進むか、自己評価を選び直してもらう
+fn wait_for_grade(stdout: &mut Stdout, cols: u16, rows: u16, grade: Grade) -> io::Result<Action> {
+    print_instructions(
+        stdout,
+        cols,
+        rows,
+        &format!(
+            "(q)uit, (m)ark memorized, (ret) {}, (1) again, (2) hard, (3) good, (4) easy",
+            grade.name()
+        ),
+    )?;
+    Ok(match wait_for_action(true)? {
+        Action::Next => Action::Grade(grade),
+        action => action,
+    })
+}
+
+/// 選択肢を `y` 行目から 1 行ずつ表示する
+/// # Arguments
+/// * `options` - 選択肢の文字列と、正解かどうか
+/// * `answered` - 回答済みの場合は正解と選んだ選択肢を色分けする
+fn print_options(
+    stdout: &mut Stdout,
+    cols: u16,
+    y: u16,
+    options: &[(String, bool)],
+    selected: usize,
+    answered: bool,
+) -> io::Result<()> {
+    let lines = options
+        .iter()
+        .enumerate()
+        .map(|(i, (option, correct))| {
+            let marker = if i == selected { "▶" } else { " " };
+            let text = format!("{marker} {}. {option}", i + 1);
+            let (color, style) = match (answered, *correct, i == selected) {
+                (true, true, _) => (Color::Green, Style::Bold),
+                (true, false, true) => (Color::Red, Style::Bold),
+                (true, false, false) => (Color::DarkGrey, Style::Plain),
+                (false, _, true) => (Color::Cyan, Style::Bold),
+                (false, _, false) => (Color::Reset, Style::Plain),
+            };
+            StyledText::new(&text, color, style)
+        })
+        .collect::<Vec<_>>();
+
+    // 選択肢の先頭をそろえるため、最も長い選択肢を基準に中央寄せする
+    let width = lines.iter().map(StyledText::width).max().unwrap_or(0);
+    let x = cols.saturating_sub(width as u16) / 2;
+    for (i, line) in lines.iter().enumerate() {
+        let y = y + i as u16;
+        execute!(
+            stdout,
+            cursor::MoveTo(0, y),
+            terminal::Clear(ClearType::CurrentLine),
+            cursor::MoveTo(x, y)
+        )?;
+        print_styled_text(stdout, line);
+    }
+    Ok(())
+}
+
+/// 入力と答えの差分、判定結果を `y` 行目から順に表示する
+fn print_judgement(
+    stdout: &mut Stdout,
+    cols: u16,
+    y: u16,
+    typed: &str,
+    judgement: &Judgement,
+) -> io::Result<()> {
+    let diffs = diff(typed, &judgement.expected);
+
+    let typed_line = if judgement.verdict == Verdict::Inflected {
+        // 活用形は正解とみなすため、差分を強調しない
+        vec![StyledText::new(typed, Color::Green, Style::Plain)]
+    } else if typed.is_empty() {
+        vec![StyledText::new(
+            "(no answer)",
+            Color::DarkGrey,
+            Style::Plain,
+        )]
+    } else {
+        diffs
+            .iter()
+            .filter_map(|diff| match *diff {
+                Diff::Equal(typed, _) => Some((typed, Color::Green, Style::Plain)),
+                Diff::Substitute(typed, _) | Diff::Extra(typed) => {
+                    Some((typed, Color::Red, Style::Underline))
+                }
+                Diff::Missing(_) => None,
+            })
+            .map(|(c, color, style)| StyledText::new(&c.to_string(), color, style))
+            .collect()
+    };
+    let expected_line = if judgement.verdict == Verdict::Inflected {
+        vec![StyledText::new(
+            &judgement.expected,
+            Color::Yellow,
+            Style::Plain,
+        )]
+    } else {
+        diffs
+            .iter()
+            .filter_map(|diff| match *diff {
+                Diff::Equal(_, expected) => Some((expected, Style::Plain)),
+                Diff::Substitute(_, expected) | Diff::Missing(expected) => {
+                    Some((expected, Style::BoldUnderline))
+                }
+                Diff::Extra(_) => None,
+            })
+            .map(|(c, style)| StyledText::new(&c.to_string(), Color::Yellow, style))
+            .collect()
+    };
+    print_lines(
+        stdout,
+        cols,
+        y,
+        &[typed_line, expected_line, vec![verdict_text(judgement)]],
+    )
+}
+
+/// 書き取った文と読み上げた文の差分を単語単位で、判定結果とともに `y` 行目から順に表示する
+fn print_dictation(
+    stdout: &mut Stdout,
+    cols: u16,
+    y: u16,
+    typed: &str,
+    judgement: &Judgement,
+) -> io::Result<()> {
+    let diffs = diff_tokens(typed, &judgement.expected);
+    // 単語の間に空白を挟んで 1 行にする
+    let join = |words: Vec<StyledText>| {
+        let space = StyledText::new(" ", Color::Reset, Style::Plain);
+        let mut line = Vec::new();
+        for word in words {
+            if !line.is_empty() {
+                line.push(space.clone());
+            }
+            line.push(word);
+        }
+        line
+    };
+
+    let typed_line = if typed.trim().is_empty() {
+        vec![StyledText::new(
+            "(no answer)",
+            Color::DarkGrey,
+            Style::Plain,
+        )]
+    } else {
+        join(
+            diffs
+                .iter()
+                .filter_map(|diff| match *diff {
+                    Diff::Equal(typed, _) => Some((typed, Color::Green, Style::Plain)),
+                    Diff::Substitute(typed, _) | Diff::Extra(typed) => {
+                        Some((typed, Color::Red, Style::Underline))
+                    }
+                    Diff::Missing(_) => None,
+                })
+                .map(|(word, color, style)| StyledText::new(word, color, style))
+                .collect(),
+        )
+    };
+    let expected_line = join(
+        diffs
+            .iter()
+            .filter_map(|diff| match *diff {
+                Diff::Equal(_, expected) => Some((expected, Style::Plain)),
+                Diff::Substitute(_, expected) | Diff::Missing(expected) => {
+                    Some((expected, Style::BoldUnderline))
+                }
+                Diff::Extra(_) => None,
+            })
+            .map(|(word, style)| StyledText::new(word, Color::Yellow, style))
+            .collect(),
+    );
+
+    print_lines(
+        stdout,
+        cols,
+        y,
+        &[typed_line, expected_line, vec![verdict_text(judgement)]],
+    )
+}
+
+fn verdict_text(judgement: &Judgement) -> StyledText {
+    match judgement.verdict {
+        Verdict::Correct => StyledText::new("✔ correct", Color::Green, Style::Bold),
+        Verdict::Inflected => StyledText::new(
+            &format!(
+                "✔ accepted (base form: {})",
+                judgement
+                    .base_form
+                    .as_deref()
+                    .unwrap_or(&judgement.expected)
+            ),
+            Color::Green,
+            Style::Bold,
+        ),
+        Verdict::Close => StyledText::new("≈ close enough", Color::Yellow, Style::Bold),
+        Verdict::Wrong => StyledText::new("✘ wrong", Color::Red, Style::Bold),
+    }
+}
+
+/// `y` 行目から 1 行ずつ、既存の表示を消してから中央寄せで表示する
+fn print_lines(
+    stdout: &mut Stdout,
+    cols: u16,
+    y: u16,
+    lines: &[Vec<StyledText>],
+) -> io::Result<()> {
+    for (i, line) in lines.iter().enumerate() {
+        let y = y + i as u16;
+        execute!(
+            stdout,
+            cursor::MoveTo(0, y),
+            terminal::Clear(ClearType::CurrentLine)
+        )?;
+        print_centered(stdout, cols, y, line);
+    }
+    Ok(())
+}
@@ -0,0 +1,247 @@
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// 初期の易しさ係数
+pub const INITIAL_EASE_FACTOR: f64 = 2.5;
+/// 易しさ係数の下限
+pub const MIN_EASE_FACTOR: f64 = 1.3;
+/// 暗記済みにした単語の復習間隔 (日)
+const MEMORIZED_INTERVAL: u32 = 60;
+/// 復習間隔がこの日数以上の単語を暗記済みとみなす
+const MATURE_INTERVAL: u32 = 21;
+
+/// 答えを見たあとの自己評価
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Grade {
+    /// 思い出せなかった (同じセッション内でもう一度出題する)
+    Again,
+    /// 思い出すのに苦労した
+    Hard,
+    /// 少し迷ったが思い出せた
+    Good,
+    /// すぐに思い出せた
+    Easy,
+}
+
+impl Grade {
+    /// SM-2 の回答品質 (0〜5) に変換する
+    pub fn quality(self) -> u8 {
+        match self {
+            Grade::Again => 1,
+            Grade::Hard => 3,
+            Grade::Good => 4,
+            Grade::Easy => 5,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+}
+
+/// SM-2 アルゴリズムによる単語ごとの学習スケジュール
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Schedule {
+    /// 易しさ係数 (大きいほど復習間隔が伸びやすい)
+    pub ease_factor: f64,
+    /// 復習間隔 (日)
+    pub interval: u32,
+    /// 連続して正解した回数
+    pub repetitions: u32,
+    /// 次回の出題日
+    pub due: NaiveDate,
+    /// 直近の自己評価
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_grade: Option<Grade>,
+}
+
+impl Schedule {
+    /// 初めて学習する単語のスケジュール
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            ease_factor: INITIAL_EASE_FACTOR,
+            interval: 0,
+            repetitions: 0,
+            due: today,
+            last_grade: None,
+        }
+    }
+
+    /// 暗記済みとして、しばらく出題しないスケジュール
+    /// 旧形式の `skip: true` もこのスケジュールに移行する
+    pub fn memorized(today: NaiveDate) -> Self {
+        Self {
+            ease_factor: INITIAL_EASE_FACTOR,
+            interval: MEMORIZED_INTERVAL,
+            repetitions: 3,
+            due: add_days(today, MEMORIZED_INTERVAL),
+            last_grade: None,
+        }
+    }
+
+    /// 自己評価をもとに次回の出題日を決める
+    pub fn grade(&mut self, grade: Grade, today: NaiveDate) {
+        self.review(grade.quality(), today);
+        self.last_grade = Some(grade);
+    }
+
+    /// 同じセッション内でもう一度出題した単語の自己評価をもとに次回の出題日を決める
+    /// 易しさ係数は最初の自己評価で更新済みのため、変えない
+    pub fn relearn(&mut self, grade: Grade, today: NaiveDate) {
+        let ease_factor = self.ease_factor;
+        self.grade(grade, today);
+        self.ease_factor = ease_factor;
+    }
+
+    /// 回答の出来 (0〜5) をもとに次回の出題日を決める
+    /// # Arguments
+    /// * `quality` - 5: 完璧, 4: 少し迷った, 3: 思い出すのに苦労した, 2 以下: 思い出せなかった
+    pub fn review(&mut self, quality: u8, today: NaiveDate) {
+        let quality = quality.min(5);
+        if quality >= 3 {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as f64 * self.ease_factor).round() as u32,
+            };
+            self.repetitions += 1;
+        } else {
+            self.repetitions = 0;
+            self.interval = 1;
+        }
+
+        let penalty = (5 - quality) as f64;
+        self.ease_factor =
+            (self.ease_factor + 0.1 - penalty * (0.08 + penalty * 0.02)).max(MIN_EASE_FACTOR);
+        self.due = add_days(today, self.interval);
+    }
+
+    /// 出題日を今日に戻す (学習の進み具合は保持する)
+    pub fn reset_due(&mut self, today: NaiveDate) {
+        self.due = today;
+    }
+
+    /// 暗記済みとみなせるほど復習間隔が伸びているか
+    pub fn is_memorized(&self) -> bool {
+        self.interval >= MATURE_INTERVAL
+    }
+
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        self.due <= today
+    }
+
+    /// 出題の優先度 (大きいほど優先)
+    /// 復習間隔に対して期限をどれだけ過ぎているかで決める
+    pub fn urgency(&self, today: NaiveDate) -> f64 {
+        let overdue = (today - self.due).num_days() as f64;
+        overdue / self.interval.max(1) as f64
+    }
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+fn add_days(date: NaiveDate, days: u32) -> NaiveDate {
+    date.checked_add_days(Days::new(days as u64))
+        .unwrap_or(NaiveDate::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_review_intervals_grow() {
+        let today = date(2025, 1, 1);
+        let mut schedule = Schedule::new(today);
+
+        schedule.review(4, today);
+        assert_eq!(schedule.interval, 1);
+        assert_eq!(schedule.due, date(2025, 1, 2));
+
+        schedule.review(4, today);
+        assert_eq!(schedule.interval, 6);
+
+        schedule.review(4, today);
+        assert_eq!(schedule.interval, 15);
+        assert_eq!(schedule.repetitions, 3);
+        assert_eq!(schedule.ease_factor, INITIAL_EASE_FACTOR);
+    }
+
+    #[test]
+    fn test_review_failure_resets_repetitions() {
+        let today = date(2025, 1, 1);
+        let mut schedule = Schedule::memorized(today);
+
+        schedule.review(1, today);
+        assert_eq!(schedule.repetitions, 0);
+        assert_eq!(schedule.interval, 1);
+        assert!(schedule.ease_factor < INITIAL_EASE_FACTOR);
+    }
+
+    #[test]
+    fn test_ease_factor_has_lower_bound() {
+        let today = date(2025, 1, 1);
+        let mut schedule = Schedule::new(today);
+        for _ in 0..20 {
+            schedule.review(0, today);
+        }
+        assert_eq!(schedule.ease_factor, MIN_EASE_FACTOR);
+    }
+
+    #[test]
+    fn test_grade_is_recorded() {
+        let today = date(2025, 1, 1);
+        let mut schedule = Schedule::new(today);
+
+        schedule.grade(Grade::Easy, today);
+        assert_eq!(schedule.last_grade, Some(Grade::Easy));
+        assert!(schedule.ease_factor > INITIAL_EASE_FACTOR);
+
+        schedule.grade(Grade::Again, today);
+        assert_eq!(schedule.last_grade, Some(Grade::Again));
+        assert_eq!(schedule.repetitions, 0);
+        assert_eq!(schedule.due, date(2025, 1, 2));
+    }
+
+    #[test]
+    fn test_relearn_keeps_ease_factor() {
+        let today = date(2025, 1, 1);
+        let mut schedule = Schedule::memorized(today);
+        schedule.grade(Grade::Again, today);
+        let ease_factor = schedule.ease_factor;
+
+        schedule.relearn(Grade::Again, today);
+        assert_eq!(schedule.ease_factor, ease_factor);
+        schedule.relearn(Grade::Good, today);
+        assert_eq!(schedule.ease_factor, ease_factor);
+        assert_eq!(schedule.repetitions, 1);
+        assert_eq!(schedule.last_grade, Some(Grade::Good));
+    }
+
+    #[test]
+    fn test_urgency() {
+        let today = date(2025, 1, 10);
+        let mut overdue = Schedule::new(today);
+        overdue.interval = 2;
+        overdue.due = date(2025, 1, 6);
+        let mut slightly_overdue = Schedule::new(today);
+        slightly_overdue.interval = 10;
+        slightly_overdue.due = date(2025, 1, 6);
+
+        assert!(overdue.is_due(today));
+        assert!(overdue.urgency(today) > slightly_overdue.urgency(today));
+        assert!(!Schedule::memorized(today).is_due(today));
+    }
+}
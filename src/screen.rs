@@ -0,0 +1,188 @@
+use crate::dictionary::Dictionary;
+use crate::styled_text::{Style, StyledText, print_styled_text, print_styled_texts};
+use crate::stylist::style_example;
+use crossterm::event::{DisableBracketedPaste, EnableBracketedPaste};
+use crossterm::style::Color;
+use crossterm::{
+    cursor, execute,
+    terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use log::warn;
+use std::io::{self, Stdout, Write};
+use std::panic;
+use unicode_width::UnicodeWidthStr;
+
+/// 単語帳ごとの進み具合
+pub struct DeckProgress<'a> {
+    pub name: &'a str,
+    /// 出題済みの数
+    pub done: usize,
+    /// 今回出題する数
+    pub total: usize,
+}
+
+/// 全体の進み具合を表示する
+/// 複数の単語帳から出題する場合は、その下に単語帳ごとの進み具合を表示し、出題中の単語の単語帳を強調する
+/// # Arguments
+/// * `decks` - 今回出題する単語帳ごとの進み具合 (空なら表示しない)
+/// * `current` - 出題中の単語の単語帳の `decks` 中のインデックス
+pub fn print_progress(
+    stdout: &mut Stdout,
+    terminal_columns: u16,
+    i: usize,
+    total: usize,
+    decks: &[DeckProgress],
+    current: usize,
+) {
+    let progress = format!("{} / {}", i + 1, total);
+    let prog_width = UnicodeWidthStr::width(progress.as_str()) as u16;
+    let prog_x = terminal_columns.saturating_sub(prog_width) / 2;
+    execute!(stdout, cursor::MoveTo(prog_x, 1)).unwrap();
+
+    let progress = StyledText::new(progress.as_str(), Color::DarkCyan, Style::Underline);
+    print_styled_text(stdout, &progress);
+
+    if decks.is_empty() {
+        return;
+    }
+    let mut line = Vec::new();
+    for (i, deck) in decks.iter().enumerate() {
+        if i > 0 {
+            line.push(StyledText::new("  ", Color::Reset, Style::Plain));
+        }
+        let text = format!("{} {}/{}", deck.name, deck.done, deck.total);
+        line.push(if i == current {
+            StyledText::new(&text, Color::Yellow, Style::Bold)
+        } else {
+            StyledText::new(&text, Color::DarkGrey, Style::Plain)
+        });
+    }
+    print_centered(stdout, terminal_columns, 2, &line);
+}
+
+pub fn print_question(
+    stdout: &mut Stdout,
+    dictionary: &Dictionary,
+    cols: u16,
+    rows: u16,
+    english: &str,
+    example: &str,
+) -> (u16, u16) {
+    let main_width = UnicodeWidthStr::width(english) as u16;
+    let x = cols.saturating_sub(main_width) / 2;
+    let y = (rows / 2).saturating_sub(2);
+    execute!(stdout, cursor::MoveTo(x, y)).unwrap();
+
+    print_styled_text(
+        stdout,
+        &StyledText::new(english, Color::Yellow, Style::Bold),
+    );
+
+    let x = cols.saturating_sub(UnicodeWidthStr::width(example) as u16) / 2;
+    let y = y + 1;
+    // 例文がない単語は、空の括弧を表示しない
+    if !example.trim().is_empty() {
+        execute!(stdout, cursor::MoveTo(x, y)).unwrap();
+        let words = style_example(dictionary, format!("({example})").as_str(), english);
+        print_styled_texts(stdout, &words);
+    }
+
+    (x, y)
+}
+
+pub fn print_instructions(
+    stdout: &mut Stdout,
+    terminal_columns: u16,
+    terminal_rows: u16,
+    instructions: &str,
+) -> io::Result<()> {
+    let instr_width = UnicodeWidthStr::width(instructions) as u16;
+    let x = terminal_columns
+        .saturating_sub(instr_width)
+        .saturating_sub(1);
+    let y = terminal_rows.saturating_sub(1);
+    execute!(
+        stdout,
+        cursor::MoveTo(0, y),
+        terminal::Clear(ClearType::CurrentLine),
+        cursor::MoveTo(x, y)
+    )?;
+    print!("{}", instructions);
+    stdout.flush()?;
+    Ok(())
+}
+
+/// 装飾付きの文字列をつなげて `y` 行目の中央に表示する
+pub fn print_centered(stdout: &mut Stdout, cols: u16, y: u16, styled_texts: &[StyledText]) {
+    let width: usize = styled_texts.iter().map(StyledText::width).sum();
+    let x = cols.saturating_sub(width as u16) / 2;
+    execute!(stdout, cursor::MoveTo(x, y)).unwrap();
+    print_styled_texts(stdout, styled_texts);
+}
+
+pub fn clear_screen(stdout: &mut Stdout) -> io::Result<()> {
+    execute!(stdout, terminal::Clear(ClearType::All))?;
+    Ok(())
+}
+
+/// 代替スクリーンと raw モードに切り替えている間の端末
+/// エラーで処理を抜けた場合も、破棄される際に元の端末に戻す
+pub struct TerminalSession;
+
+impl TerminalSession {
+    pub fn enter(stdout: &mut Stdout) -> io::Result<Self> {
+        switch_to_alternate_screen(stdout)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        if let Err(e) = restore_terminal() {
+            warn!("Failed to restore the terminal: {}", e);
+        }
+    }
+}
+
+/// パニックのメッセージを表示する前に端末を元に戻すフックを設定する
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+}
+
+/// raw モードであれば元の端末に戻す (すでに戻っている場合は何もしない)
+fn restore_terminal() -> io::Result<()> {
+    if !terminal::is_raw_mode_enabled()? {
+        return Ok(());
+    }
+    switch_to_main_screen(&mut io::stdout())
+}
+
+fn switch_to_alternate_screen(stdout: &mut Stdout) -> io::Result<()> {
+    execute!(stdout, EnterAlternateScreen)?;
+    terminal::enable_raw_mode()?;
+    // 貼り付けた文字列を 1 回の入力として受け取る
+    execute!(stdout, cursor::Hide, EnableBracketedPaste)?;
+    Ok(())
+}
+
+fn switch_to_main_screen(stdout: &mut Stdout) -> io::Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(
+        stdout,
+        DisableBracketedPaste,
+        cursor::Show,
+        LeaveAlternateScreen
+    )?;
+    execute!(
+        stdout,
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(0, 0)
+    )?;
+    println!();
+    stdout.flush()?;
+    Ok(())
+}
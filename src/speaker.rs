@@ -1,23 +1,139 @@
-use std::process::{Child, Command};
+use clap::ValueEnum;
+use log::{info, warn};
+use std::env;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+/// 読み上げに使う音声合成エンジン
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// macOS の `say`
+    Say,
+    /// `espeak-ng`
+    EspeakNg,
+    /// speech-dispatcher の `spd-say`
+    SpdSay,
+    /// 読み上げない
+    Silent,
+}
+
+impl Backend {
+    /// 自動検出の際に試す順番
+    const CANDIDATES: [Backend; 3] = [Backend::Say, Backend::EspeakNg, Backend::SpdSay];
+
+    /// `PATH` から利用可能なエンジンを探す。見つからなければ `Silent` になる
+    pub fn detect() -> Self {
+        Self::CANDIDATES
+            .into_iter()
+            .find(|backend| backend.program().is_some_and(is_in_path))
+            .unwrap_or(Backend::Silent)
+    }
+
+    fn program(self) -> Option<&'static str> {
+        match self {
+            Backend::Say => Some("say"),
+            Backend::EspeakNg => Some("espeak-ng"),
+            Backend::SpdSay => Some("spd-say"),
+            Backend::Silent => None,
+        }
+    }
+
+    fn command(self, text: &str) -> Option<Command> {
+        let mut command = Command::new(self.program()?);
+        match self {
+            Backend::Say => command.args(["-v", "Samantha", text]),
+            Backend::EspeakNg => command.args(["-v", "en-us", text]),
+            Backend::SpdSay => command.args(["-l", "en", "-w", text]),
+            Backend::Silent => unreachable!("Silent has no program"),
+        };
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        Some(command)
+    }
+}
 
 pub struct Speaker {
+    backend: Backend,
     process: Option<Child>,
 }
 
 impl Speaker {
-    pub fn new() -> Self {
-        Self { process: None }
+    /// `backend` が `None` の場合は `PATH` から自動検出する
+    pub fn new(backend: Option<Backend>) -> Self {
+        let backend = backend.unwrap_or_else(Backend::detect);
+        info!("Using speech backend: {:?}", backend);
+        Self {
+            backend,
+            process: None,
+        }
+    }
+
+    /// 読み上げが無効になっているかどうか
+    pub fn is_silent(&self) -> bool {
+        self.backend == Backend::Silent
     }
 
-    #[cfg(target_os = "macos")]
     pub fn speak(&mut self, text: &str) {
+        self.stop();
+        let Some(mut command) = self.backend.command(text) else {
+            return;
+        };
+        match command.spawn() {
+            Ok(child) => self.process = Some(child),
+            Err(e) => {
+                warn!(
+                    "Failed to run {:?} ({}); speech is disabled for this session",
+                    self.backend, e
+                );
+                self.backend = Backend::Silent;
+            }
+        }
+    }
+
+    /// 読み上げ中の音声を止める
+    pub fn stop(&mut self) {
         if let Some(mut child) = self.process.take() {
-            child.kill().expect("failed to kill say");
+            if let Err(e) = child.kill() {
+                warn!("Failed to stop {:?}: {}", self.backend, e);
+            }
+            // ゾンビプロセスを残さないよう回収する
+            let _ = child.wait();
+
+            if self.backend == Backend::SpdSay {
+                // spd-say はデーモンが読み上げるため、クライアントを止めるだけでは止まらない
+                let _ = Command::new("spd-say")
+                    .arg("--cancel")
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+            }
         }
-        let child = Command::new("say")
-            .args(["-v", "Samantha", text])
-            .spawn()
-            .expect("failed to execute say");
-        self.process = Some(child);
     }
 }
+
+impl Drop for Speaker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn is_in_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| is_executable(&dir.join(program))))
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
@@ -0,0 +1,424 @@
+use crate::deck::Deck;
+use crate::error::{Error, Result};
+use crate::matcher::normalize;
+use crate::word::Word;
+use csv::{ReaderBuilder, WriterBuilder};
+use log::warn;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// UTF-8 の BOM (Excel で保存した CSV の先頭に付く)
+const BOM: &str = "\u{feff}";
+
+/// 書き出す列 (学習の進捗の列を含む)
+const EXPORT_HEADERS: [&str; 9] = [
+    "id",
+    "english",
+    "japanese",
+    "example",
+    "due",
+    "interval",
+    "ease_factor",
+    "repetitions",
+    "last_grade",
+];
+
+/// 表計算ソフトとやり取りするファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// カンマ区切り
+    Csv,
+    /// タブ区切り
+    Tsv,
+}
+
+impl TableFormat {
+    fn delimiter(self) -> u8 {
+        match self {
+            Self::Csv => b',',
+            Self::Tsv => b'\t',
+        }
+    }
+}
+
+/// 取り込む単語と同じ英単語が単語帳にある場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OnDuplicate {
+    /// 取り込まない
+    Skip,
+    /// 単語帳の日本語訳と例文を、取り込むファイルで空でないものに置き換える (id と学習の進捗は残す)
+    Update,
+    /// 別の単語として追加する
+    Keep,
+}
+
+/// 取り込む列の指定 (見出しまたは 1 始まりの列番号)
+/// 指定がない場合は `english`・`japanese`・`example` という見出しの列を使う
+#[derive(Debug, Clone, Default)]
+pub struct Columns {
+    pub english: Option<String>,
+    pub japanese: Option<String>,
+    pub example: Option<String>,
+}
+
+/// 取り込みの結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// 追加した単語数
+    pub added: usize,
+    /// 日本語訳や例文を置き換えた単語数
+    pub updated: usize,
+    /// 重複のため取り込まなかった単語数
+    pub skipped: usize,
+}
+
+/// CSV/TSV ファイルから単語を読み込む
+/// `id` という見出しの列があれば、その値を単語の id にする
+/// # Arguments
+/// * `has_header` - 1 行目が見出しかどうか
+pub fn read_table(
+    path: &Path,
+    format: TableFormat,
+    columns: &Columns,
+    has_header: bool,
+) -> Result<Vec<Word>> {
+    let content = fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::InvalidData => Error::Import {
+            path: path.to_path_buf(),
+            message: "the file is not UTF-8 text (save it as \"CSV UTF-8\")".to_string(),
+        },
+        _ => io::Error::new(e.kind(), format!("{}: {}", path.display(), e)).into(),
+    })?;
+    parse_table(path, &content, format, columns, has_header)
+}
+
+fn parse_table(
+    path: &Path,
+    content: &str,
+    format: TableFormat,
+    columns: &Columns,
+    has_header: bool,
+) -> Result<Vec<Word>> {
+    let content = content.strip_prefix(BOM).unwrap_or(content);
+    let mut reader = ReaderBuilder::new()
+        .delimiter(format.delimiter())
+        .has_headers(has_header)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers = if has_header {
+        Some(reader.headers().map_err(|e| csv_error(path, e))?.clone())
+    } else {
+        None
+    };
+    let headers = headers
+        .as_ref()
+        .map(|headers| headers.iter().collect::<Vec<_>>());
+    let headers = headers.as_deref();
+
+    let english = resolve_column(path, headers, columns.english.as_deref(), "english", 0)?
+        .ok_or_else(|| missing_column(path, headers, "english"))?;
+    let japanese = resolve_column(path, headers, columns.japanese.as_deref(), "japanese", 1)?
+        .ok_or_else(|| missing_column(path, headers, "japanese"))?;
+    let example = resolve_column(path, headers, columns.example.as_deref(), "example", 2)?;
+    let id = headers.and_then(|headers| find_header(headers, "id"));
+
+    let mut words = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| csv_error(path, e))?;
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map_or("", str::trim)
+        };
+        if field(Some(english)).is_empty() {
+            if record.iter().any(|field| !field.trim().is_empty()) {
+                let line = record.position().map_or(0, |position| position.line());
+                warn!(
+                    "{}:{}: skipped a row without an English word",
+                    path.display(),
+                    line
+                );
+            }
+            continue;
+        }
+        if field(Some(japanese)).is_empty() {
+            let line = record.position().map_or(0, |position| position.line());
+            warn!(
+                "{}:{}: skipped \"{}\", which has no Japanese meaning",
+                path.display(),
+                line,
+                field(Some(english))
+            );
+            continue;
+        }
+        let mut word = Word::new(field(Some(english)), field(Some(japanese)), field(example));
+        if !field(id).is_empty() {
+            word.id = field(id).to_string();
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// 列の指定を 0 始まりの列番号にする
+/// 指定がなければ、見出しが `name` の列 (見出しがない場合は `position` 番目の列) を使う
+pub fn resolve_column(
+    path: &Path,
+    headers: Option<&[&str]>,
+    spec: Option<&str>,
+    name: &str,
+    position: usize,
+) -> Result<Option<usize>> {
+    let Some(spec) = spec else {
+        return Ok(match headers {
+            Some(headers) => find_header(headers, name),
+            None => Some(position),
+        });
+    };
+    if let Ok(number) = spec.trim().parse::<usize>()
+        && number > 0
+    {
+        return Ok(Some(number - 1));
+    }
+    match headers {
+        Some(headers) => find_header(headers, spec)
+            .map(Some)
+            .ok_or_else(|| missing_column(path, Some(headers), spec)),
+        None => Err(Error::Import {
+            path: path.to_path_buf(),
+            message: format!(
+                "use a column number instead of \"{spec}\" for a file without a header"
+            ),
+        }),
+    }
+}
+
+/// 見出しが `name` の列を探す (大文字と小文字、前後の空白は区別しない)
+pub fn find_header(headers: &[&str], name: &str) -> Option<usize> {
+    headers
+        .iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(name.trim()))
+}
+
+fn missing_column(path: &Path, headers: Option<&[&str]>, name: &str) -> Error {
+    let available = headers
+        .map(|headers| headers.join(", "))
+        .unwrap_or_default();
+    Error::Import {
+        path: path.to_path_buf(),
+        message: format!("column \"{name}\" was not found (columns: {available})"),
+    }
+}
+
+fn csv_error(path: &Path, error: csv::Error) -> Error {
+    Error::Import {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    }
+}
+
+/// 読み込んだ単語を単語帳に加える
+/// 同じ id か同じ英単語 (表記ゆれを揃えて比べる) の単語が単語帳や取り込む単語の中に既にある場合は `on_duplicate` に従う
+pub fn merge(deck: &mut Deck, words: Vec<Word>, on_duplicate: OnDuplicate) -> ImportSummary {
+    let mut ids = HashMap::new();
+    let mut index = HashMap::new();
+    for (i, word) in deck.words.iter().enumerate() {
+        ids.entry(word.id.clone()).or_insert(i);
+        index.entry(normalize(&word.english)).or_insert(i);
+    }
+
+    let mut summary = ImportSummary::default();
+    for word in words {
+        let key = normalize(&word.english);
+        let duplicate = ids.get(&word.id).or_else(|| index.get(&key));
+        match (duplicate, on_duplicate) {
+            (Some(_), OnDuplicate::Skip) => summary.skipped += 1,
+            (Some(&i), OnDuplicate::Update) => {
+                update_word(&mut deck.words[i], word);
+                summary.updated += 1;
+            }
+            _ => {
+                ids.entry(word.id.clone()).or_insert(deck.words.len());
+                index.entry(key).or_insert(deck.words.len());
+                deck.words.push(word);
+                summary.added += 1;
+            }
+        }
+    }
+    summary
+}
+
+/// 取り込んだ単語の日本語訳と例文のうち、空でないもので最初の意味を置き換える
+/// 2 つ目以降の意味はそのまま残す
+fn update_word(word: &mut Word, imported: Word) {
+    let Some(imported) = imported.senses.into_iter().next() else {
+        return;
+    };
+    let Some(sense) = word.senses.first_mut() else {
+        word.senses.push(imported);
+        return;
+    };
+    if !imported.meaning.is_empty() {
+        sense.meaning = imported.meaning;
+    }
+    if !imported.examples.is_empty() {
+        sense.examples = imported.examples;
+    }
+}
+
+/// 単語を学習の進捗とともに CSV/TSV で書き出す
+/// 意味が複数ある単語は、日本語訳をまとめ、最初の例文だけを書き出す
+/// # Arguments
+/// * `bom` - 先頭に BOM を付けるかどうか (Excel で文字化けさせないため)
+pub fn write_table<W: Write>(
+    mut writer: W,
+    words: &[Word],
+    format: TableFormat,
+    bom: bool,
+) -> io::Result<()> {
+    if bom {
+        writer.write_all(BOM.as_bytes())?;
+    }
+    let mut writer = WriterBuilder::new()
+        .delimiter(format.delimiter())
+        .from_writer(writer);
+    writer.write_record(EXPORT_HEADERS)?;
+    for word in words {
+        let mut record = vec![
+            word.id.clone(),
+            word.english.clone(),
+            word.meaning(),
+            word.examples().next().unwrap_or_default().to_string(),
+        ];
+        match &word.schedule {
+            Some(schedule) => record.extend([
+                schedule.due.to_string(),
+                schedule.interval.to_string(),
+                format!("{:.2}", schedule.ease_factor),
+                schedule.repetitions.to_string(),
+                schedule
+                    .last_grade
+                    .map(|grade| grade.name().to_string())
+                    .unwrap_or_default(),
+            ]),
+            None => record.resize(EXPORT_HEADERS.len(), String::new()),
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::{Grade, Schedule};
+    use chrono::NaiveDate;
+
+    fn parse(content: &str, format: TableFormat, columns: &Columns, has_header: bool) -> Vec<Word> {
+        parse_table(Path::new("words.csv"), content, format, columns, has_header).unwrap()
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let content = "\u{feff}Example,English,Japanese\n\"I run, every day.\",run,走る\n,,\nI walk.,walk, \nI eat.,eat,食べる\n";
+        let words = parse(content, TableFormat::Csv, &Columns::default(), true);
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].english, "run");
+        assert_eq!(words[0].meaning(), "走る");
+        assert_eq!(
+            words[0].examples().collect::<Vec<_>>(),
+            vec!["I run, every day."]
+        );
+
+        let columns = Columns {
+            english: Some("2".to_string()),
+            japanese: Some("1".to_string()),
+            example: None,
+        };
+        let words = parse("走る\trun\n", TableFormat::Tsv, &columns, false);
+        assert_eq!(words[0].english, "run");
+        assert_eq!(words[0].meaning(), "走る");
+        assert_eq!(words[0].examples().count(), 0);
+
+        let columns = Columns {
+            english: Some("word".to_string()),
+            ..Columns::default()
+        };
+        let error = parse_table(
+            Path::new("words.csv"),
+            "english,japanese\n",
+            TableFormat::Csv,
+            &columns,
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cannot import words.csv: column \"word\" was not found (columns: english, japanese)"
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut deck = Deck::default();
+        deck.words.push(Word::new("run", "走る", "I run."));
+        let id = deck.words[0].id.clone();
+        let imported = vec![
+            Word::new("Run", "駆ける", ""),
+            Word::new("eat", "食べる", ""),
+            Word::new("eat", "食事する", ""),
+        ];
+
+        let summary = merge(&mut deck.clone(), imported.clone(), OnDuplicate::Skip);
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 1,
+                updated: 0,
+                skipped: 2
+            }
+        );
+
+        let summary = merge(&mut deck, imported, OnDuplicate::Update);
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 1,
+                updated: 2,
+                skipped: 0
+            }
+        );
+        assert_eq!(deck.words[0].id, id);
+        assert_eq!(deck.words[0].meaning(), "駆ける");
+        assert_eq!(deck.words[0].examples().collect::<Vec<_>>(), vec!["I run."]);
+        assert_eq!(deck.words[1].meaning(), "食事する");
+    }
+
+    #[test]
+    fn test_write_table() {
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut words = vec![
+            Word::new("run", "走る", "I run, every day."),
+            Word::new("eat", "食べる", ""),
+        ];
+        words[0].id = "a".to_string();
+        words[1].id = "b".to_string();
+        let mut schedule = Schedule::new(today);
+        schedule.grade(Grade::Good, today);
+        words[0].schedule = Some(schedule);
+
+        let mut output = Vec::new();
+        write_table(&mut output, &words, TableFormat::Csv, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(
+            output,
+            "\u{feff}id,english,japanese,example,due,interval,ease_factor,repetitions,last_grade\n\
+             a,run,走る,\"I run, every day.\",2025-01-02,1,2.50,1,good\n\
+             b,eat,食べる,,,,,,\n"
+        );
+    }
+}
@@ -0,0 +1,444 @@
+use crate::history::{Event, EventAction};
+use crate::scheduler::Grade;
+use crate::styled_text::{Style, StyledText, print_styled_text, print_styled_texts};
+use crate::word::Word;
+use chrono::{Datelike, Days, NaiveDate};
+use crossterm::style::Color;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+use unicode_width::UnicodeWidthStr;
+
+/// 日ごとの学習単語数を表示する日数
+const RECENT_DAYS: u64 = 7;
+/// 苦手な単語を表示する件数
+const HARDEST_WORDS: usize = 5;
+/// ヒートマップに表示する週数
+const HEATMAP_WEEKS: u64 = 20;
+
+/// 思い出せなかった回数の多い単語
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardWord {
+    pub english: String,
+    /// 「Again」と評価した回数
+    pub again: usize,
+    /// 現在の易しさ係数
+    pub ease_factor: Option<f64>,
+}
+
+/// 単語帳と学習履歴から集計した統計
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// 単語数
+    pub total: usize,
+    /// 未学習の単語数
+    pub new: usize,
+    /// 学習中の単語数
+    pub learning: usize,
+    /// 暗記済みの単語数
+    pub memorized: usize,
+    /// 今日出題される単語数
+    pub due: usize,
+    /// 自己評価をつけた回数
+    pub graded: usize,
+    /// 自己評価のうち「Again」以外だった回数
+    pub recalled: usize,
+    /// 答えを表示するまでの平均時間
+    pub average_response_time: Option<Duration>,
+    /// 日ごとの学習した単語数 (重複を除く)
+    pub words_per_day: BTreeMap<NaiveDate, usize>,
+    /// 日ごとの復習回数
+    pub reviews_per_day: BTreeMap<NaiveDate, usize>,
+    /// 苦手な単語 (苦手な順)
+    pub hardest: Vec<HardWord>,
+    /// タグごとの単語数 (多い順、同じ数ならタグの名前順)
+    pub tags: Vec<(String, usize)>,
+}
+
+impl Stats {
+    pub fn new(words: &[Word], events: &[Event], today: NaiveDate) -> Self {
+        let new = words.iter().filter(|word| word.schedule.is_none()).count();
+        let memorized = words
+            .iter()
+            .filter(|word| word.schedule.as_ref().is_some_and(|s| s.is_memorized()))
+            .count();
+        let due = words.iter().filter(|word| word.is_due(today)).count();
+
+        let grades = events.iter().filter_map(|event| match event.action {
+            EventAction::Grade { grade } => Some(grade),
+            _ => None,
+        });
+        let graded = grades.clone().count();
+        let recalled = grades.filter(|&grade| grade != Grade::Again).count();
+
+        // id のない古い記録は、記録した英単語から今の単語を探して対応付ける
+        let ids = words
+            .iter()
+            .map(|word| (word.english.as_str(), word.id.as_str()))
+            .collect::<HashMap<_, _>>();
+        let word_id = |event: &'_ Event| -> String {
+            event
+                .id
+                .as_deref()
+                .or_else(|| ids.get(event.word.as_str()).copied())
+                .unwrap_or(&event.word)
+                .to_string()
+        };
+
+        let mut words_per_day = BTreeMap::<NaiveDate, HashSet<String>>::new();
+        let mut reviews_per_day = BTreeMap::<NaiveDate, usize>::new();
+        let mut again = HashMap::<String, usize>::new();
+        for event in events {
+            let date = event.timestamp.date_naive();
+            match event.action {
+                EventAction::Reveal => continue,
+                EventAction::Grade {
+                    grade: Grade::Again,
+                } => *again.entry(word_id(event)).or_default() += 1,
+                _ => {}
+            }
+            words_per_day
+                .entry(date)
+                .or_default()
+                .insert(word_id(event));
+            *reviews_per_day.entry(date).or_default() += 1;
+        }
+
+        let mut hardest = words
+            .iter()
+            .filter_map(|word| {
+                let again = again.get(&word.id).copied()?;
+                Some(HardWord {
+                    english: word.english.clone(),
+                    again,
+                    ease_factor: word.schedule.as_ref().map(|s| s.ease_factor),
+                })
+            })
+            .collect::<Vec<_>>();
+        hardest.sort_by(|a, b| {
+            b.again.cmp(&a.again).then(
+                a.ease_factor
+                    .unwrap_or(f64::MAX)
+                    .total_cmp(&b.ease_factor.unwrap_or(f64::MAX)),
+            )
+        });
+        hardest.truncate(HARDEST_WORDS);
+
+        let mut tags = BTreeMap::<&str, usize>::new();
+        for tag in words.iter().flat_map(|word| &word.tags) {
+            *tags.entry(tag).or_default() += 1;
+        }
+        let mut tags = tags
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect::<Vec<_>>();
+        tags.sort_by_key(|&(_, count)| Reverse(count));
+
+        Self {
+            total: words.len(),
+            new,
+            learning: words.len() - new - memorized,
+            memorized,
+            due,
+            graded,
+            recalled,
+            average_response_time: average_response_time(events),
+            words_per_day: words_per_day
+                .into_iter()
+                .map(|(date, words)| (date, words.len()))
+                .collect(),
+            reviews_per_day,
+            hardest,
+            tags,
+        }
+    }
+
+    /// 自己評価のうち思い出せた割合
+    pub fn retention_rate(&self) -> Option<f64> {
+        (self.graded > 0).then(|| self.recalled as f64 / self.graded as f64)
+    }
+}
+
+/// 答えを表示するまでの平均時間
+/// 答えを表示した記録がない場合は、自己評価までの時間で代用する
+fn average_response_time(events: &[Event]) -> Option<Duration> {
+    let mut times = response_times(events, |action| matches!(action, EventAction::Reveal));
+    if times.is_empty() {
+        times = response_times(events, |action| matches!(action, EventAction::Grade { .. }));
+    }
+    if times.is_empty() {
+        return None;
+    }
+    let sum: u64 = times.iter().sum();
+    Some(Duration::from_millis(sum / times.len() as u64))
+}
+
+fn response_times(events: &[Event], filter: impl Fn(&EventAction) -> bool) -> Vec<u64> {
+    events
+        .iter()
+        .filter(|event| filter(&event.action))
+        .filter_map(|event| event.response_time_ms)
+        .collect()
+}
+
+/// ヒートマップの各マス (行が曜日、列が週)
+/// 今日より後の日は `None` になる
+fn heatmap_cells(
+    reviews_per_day: &BTreeMap<NaiveDate, usize>,
+    today: NaiveDate,
+    weeks: u64,
+) -> Vec<Vec<Option<usize>>> {
+    let this_monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    let start = this_monday - Days::new((weeks - 1) * 7);
+    (0..7)
+        .map(|weekday| {
+            (0..weeks)
+                .map(|week| {
+                    let date = start + Days::new(week * 7 + weekday);
+                    (date <= today).then(|| reviews_per_day.get(&date).copied().unwrap_or(0))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn heatmap_cell(count: usize, max: usize) -> StyledText {
+    let (color, style) = match count {
+        0 => (Color::DarkGrey, Style::Plain),
+        n if n * 3 <= max => (Color::DarkGreen, Style::Plain),
+        n if n * 3 <= max * 2 => (Color::Green, Style::Plain),
+        _ => (Color::Green, Style::Bold),
+    };
+    StyledText::new("■ ", color, style)
+}
+
+fn print_heading(stdout: &mut Stdout, heading: &str) {
+    println!();
+    print_styled_text(
+        stdout,
+        &StyledText::new(heading, Color::Yellow, Style::Bold),
+    );
+    println!();
+}
+
+pub fn print_stats(
+    stdout: &mut Stdout,
+    file_path: &str,
+    stats: &Stats,
+    today: NaiveDate,
+) -> io::Result<()> {
+    print_heading(stdout, file_path);
+    println!("  Words      {:>6}", stats.total);
+    println!(
+        "  Active     {:>6} (new {}, learning {})",
+        stats.new + stats.learning,
+        stats.new,
+        stats.learning
+    );
+    println!("  Memorized  {:>6}", stats.memorized);
+    println!("  Due today  {:>6}", stats.due);
+
+    print_heading(stdout, "Tags");
+    if stats.tags.is_empty() {
+        println!("  -");
+    }
+    let tag_width = stats
+        .tags
+        .iter()
+        .map(|(tag, _)| UnicodeWidthStr::width(tag.as_str()))
+        .max()
+        .unwrap_or(0);
+    for (tag, count) in &stats.tags {
+        let padding = tag_width - UnicodeWidthStr::width(tag.as_str());
+        println!("  {tag}{}  {count:>6}", " ".repeat(padding));
+    }
+
+    print_heading(stdout, "Reviews");
+    match stats.retention_rate() {
+        Some(rate) => println!(
+            "  Retention rate      {:.1}% ({} / {})",
+            rate * 100.0,
+            stats.recalled,
+            stats.graded
+        ),
+        None => println!("  Retention rate      -"),
+    }
+    match stats.average_response_time {
+        Some(time) => println!("  Avg. response time  {:.1}s", time.as_secs_f64()),
+        None => println!("  Avg. response time  -"),
+    }
+
+    print_heading(
+        stdout,
+        &format!("Words reviewed per day (last {RECENT_DAYS} days)"),
+    );
+    for days_ago in (0..RECENT_DAYS).rev() {
+        let date = today - Days::new(days_ago);
+        let count = stats.words_per_day.get(&date).copied().unwrap_or(0);
+        println!("  {date}  {count:>4}");
+    }
+
+    print_heading(stdout, "Hardest words");
+    if stats.hardest.is_empty() {
+        println!("  -");
+    }
+    for (i, word) in stats.hardest.iter().enumerate() {
+        let ease = word
+            .ease_factor
+            .map_or("-".to_string(), |ease| format!("{ease:.2}"));
+        println!(
+            "  {}. {} (again {}, ease {})",
+            i + 1,
+            word.english,
+            word.again,
+            ease
+        );
+    }
+
+    print_heading(stdout, &format!("Activity (last {HEATMAP_WEEKS} weeks)"));
+    let cells = heatmap_cells(&stats.reviews_per_day, today, HEATMAP_WEEKS);
+    let max = cells.iter().flatten().flatten().copied().max().unwrap_or(0);
+    let labels = ["Mon", "", "Wed", "", "Fri", "", "Sun"];
+    for (label, row) in labels.iter().zip(cells) {
+        print!("  {label:<4}");
+        let row = row
+            .into_iter()
+            .map(|cell| match cell {
+                Some(count) => heatmap_cell(count, max),
+                None => StyledText::new("  ", Color::Reset, Style::Plain),
+            })
+            .collect::<Vec<_>>();
+        print_styled_texts(stdout, &row);
+        println!();
+    }
+    println!();
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::Schedule;
+    use chrono::{Local, TimeZone};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn word(english: &str, schedule: Option<Schedule>) -> Word {
+        Word {
+            schedule,
+            ..Word::new(english, "", "")
+        }
+    }
+
+    fn event(word: &str, date: NaiveDate, action: EventAction, response_time_ms: u64) -> Event {
+        Event {
+            id: None,
+            word: word.to_string(),
+            timestamp: Local
+                .from_local_datetime(&date.and_hms_opt(12, 0, 0).unwrap())
+                .unwrap(),
+            action,
+            response_time_ms: Some(response_time_ms),
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let today = date(2025, 1, 10);
+        let mut learning = Schedule::new(today);
+        learning.grade(Grade::Again, today);
+        let mut words = vec![
+            word("run", None),
+            word("eat", Some(learning)),
+            word("be", Some(Schedule::memorized(today))),
+        ];
+        words[0].tags = vec!["verb".to_string(), "toeic".to_string()];
+        words[1].tags = vec!["verb".to_string()];
+        let again = EventAction::Grade {
+            grade: Grade::Again,
+        };
+        let good = EventAction::Grade { grade: Grade::Good };
+        let events = vec![
+            event("eat", date(2025, 1, 9), EventAction::Reveal, 1000),
+            event("eat", date(2025, 1, 9), again, 1500),
+            event("eat", date(2025, 1, 9), EventAction::Reveal, 3000),
+            event("eat", date(2025, 1, 9), good, 3500),
+            event("be", date(2025, 1, 10), good, 500),
+            event("run", date(2025, 1, 10), again, 500),
+            event("run", date(2025, 1, 10), again, 500),
+        ];
+
+        let stats = Stats::new(&words, &events, today);
+
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.new, 1);
+        assert_eq!(stats.learning, 1);
+        assert_eq!(stats.memorized, 1);
+        assert_eq!(stats.due, 1);
+        assert_eq!(stats.graded, 5);
+        assert_eq!(stats.recalled, 2);
+        assert_eq!(stats.retention_rate(), Some(0.4));
+        assert_eq!(
+            stats.average_response_time,
+            Some(Duration::from_millis(2000))
+        );
+        assert_eq!(stats.words_per_day.get(&date(2025, 1, 9)), Some(&1));
+        assert_eq!(stats.words_per_day.get(&date(2025, 1, 10)), Some(&2));
+        assert_eq!(stats.reviews_per_day.get(&date(2025, 1, 9)), Some(&2));
+        assert_eq!(
+            stats
+                .hardest
+                .iter()
+                .map(|word| (word.english.as_str(), word.again))
+                .collect::<Vec<_>>(),
+            vec![("run", 2), ("eat", 1)]
+        );
+        assert_eq!(
+            stats.tags,
+            vec![("verb".to_string(), 2), ("toeic".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_stats_after_rename() {
+        let today = date(2025, 1, 10);
+        let mut words = vec![word("run", None)];
+        let again = EventAction::Grade {
+            grade: Grade::Again,
+        };
+        let mut events = vec![event("run", date(2025, 1, 9), again, 500)];
+        events.push(Event {
+            id: Some(words[0].id.clone()),
+            ..event("run", date(2025, 1, 10), again, 500)
+        });
+
+        // id のある記録は、英単語を書き換えた後も同じ単語の記録として数える
+        words[0].english = "sprint".to_string();
+        let stats = Stats::new(&words, &events, today);
+
+        assert_eq!(stats.hardest.len(), 1);
+        assert_eq!(stats.hardest[0].english, "sprint");
+        assert_eq!(stats.hardest[0].again, 1);
+        assert_eq!(stats.words_per_day.get(&date(2025, 1, 10)), Some(&1));
+    }
+
+    #[test]
+    fn test_heatmap_cells() {
+        // 2025-01-08 は水曜日
+        let today = date(2025, 1, 8);
+        let reviews = BTreeMap::from([(date(2025, 1, 6), 3), (date(2024, 12, 31), 1)]);
+
+        let cells = heatmap_cells(&reviews, today, 2);
+
+        assert_eq!(cells.len(), 7);
+        assert_eq!(cells[0], vec![Some(0), Some(3)]);
+        assert_eq!(cells[1], vec![Some(1), Some(0)]);
+        assert_eq!(cells[2], vec![Some(0), Some(0)]);
+        assert_eq!(cells[3], vec![Some(0), None]);
+        assert_eq!(cells[6], vec![Some(0), None]);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::database::SqliteStorage;
+use crate::deck::{Deck, load_deck, read_deck, write_deck};
+use crate::error::Result;
+use crate::history::{Event, History, read_history};
+use crate::progress::Progress;
+use crate::word::Word;
+use std::path::Path;
+
+/// SQLite の単語帳とみなす拡張子
+const DATABASE_EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+/// 単語帳と、その学習スケジュール・学習履歴の保存先
+pub trait Storage {
+    /// 学習スケジュールを反映した単語帳を読み込む
+    fn load(&mut self) -> Result<Deck>;
+
+    /// 検査のため、単語帳を書き換えずにそのまま読み込む (学習スケジュールは反映しない)
+    fn inspect(&mut self) -> Result<Deck>;
+
+    /// 単語帳全体を学習スケジュールとともに保存する
+    fn save(&mut self, deck: &Deck) -> Result<()>;
+
+    /// 単語の学習スケジュールだけを保存する
+    fn save_schedules(&mut self, words: &[Word]) -> Result<()>;
+
+    /// 学習履歴を記録した順に読み込む
+    fn history(&mut self) -> Result<Vec<Event>>;
+
+    /// 学習履歴に記録を追加する
+    fn record(&mut self, events: &[Event]) -> Result<()>;
+}
+
+/// 拡張子から単語帳の保存先を選ぶ (`.db`・`.sqlite`・`.sqlite3` は SQLite、それ以外は JSON)
+pub fn open(path: &str) -> Box<dyn Storage> {
+    if is_database(path) {
+        Box::new(SqliteStorage::new(path))
+    } else {
+        Box::new(JsonStorage::new(path))
+    }
+}
+
+pub fn is_database(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|extension| {
+        DATABASE_EXTENSIONS
+            .iter()
+            .any(|database| extension.eq_ignore_ascii_case(database))
+    })
+}
+
+/// JSON の単語ファイル
+/// 学習スケジュールは利用者ごとの進捗ファイルに、学習履歴は単語ファイルの隣の JSON Lines に保存する
+pub struct JsonStorage {
+    path: String,
+    progress: Option<Progress>,
+    history: Option<History>,
+}
+
+impl JsonStorage {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            progress: None,
+            history: None,
+        }
+    }
+
+    /// 進捗を開く (まだ開いていなければ、`words` に書かれた旧形式の学習スケジュールを取り込む)
+    fn progress(&mut self, words: &[Word]) -> Result<&mut Progress> {
+        if self.progress.is_none() {
+            self.progress = Some(Progress::open(&self.path, words)?);
+        }
+        Ok(self
+            .progress
+            .as_mut()
+            .expect("the progress was just opened"))
+    }
+}
+
+impl Storage for JsonStorage {
+    fn load(&mut self) -> Result<Deck> {
+        let mut deck = read_deck(&self.path)?;
+        self.progress(&deck.words)?.apply(&mut deck.words)?;
+        Ok(deck)
+    }
+
+    fn inspect(&mut self) -> Result<Deck> {
+        load_deck(&self.path)
+    }
+
+    fn save(&mut self, deck: &Deck) -> Result<()> {
+        // 進捗ファイルの名前は単語ファイルの絶対パスから決まるため、単語ファイルを先に作る
+        write_deck(&self.path, deck)?;
+        self.save_schedules(&deck.words)
+    }
+
+    fn save_schedules(&mut self, words: &[Word]) -> Result<()> {
+        let progress = self.progress(words)?;
+        words.iter().for_each(|word| progress.update(word));
+        Ok(progress.save()?)
+    }
+
+    fn history(&mut self) -> Result<Vec<Event>> {
+        Ok(read_history(&self.path)?)
+    }
+
+    fn record(&mut self, events: &[Event]) -> Result<()> {
+        if self.history.is_none() {
+            self.history = Some(History::open(&self.path)?);
+        }
+        let history = self.history.as_mut().expect("the history was just opened");
+        Ok(history.record(events)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_database() {
+        assert!(is_database("words.db"));
+        assert!(is_database("decks/words.SQLite3"));
+        assert!(!is_database("words.json"));
+        assert!(!is_database("db"));
+    }
+}
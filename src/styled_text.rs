@@ -1,6 +1,7 @@
 use std::io::{Stdout, Write};
 use crossterm::execute;
 use crossterm::style::{Attribute, Color};
+use unicode_width::UnicodeWidthStr;
 
 pub fn print_styled_text(stdout: &mut Stdout, styled_text: &StyledText) {
     set_color(stdout, styled_text.color);
@@ -35,6 +36,11 @@ impl StyledText {
             underline: style == Style::Underline || style == Style::BoldUnderline,
         }
     }
+
+    /// 端末上での表示幅
+    pub fn width(&self) -> usize {
+        UnicodeWidthStr::width(self.text.as_str())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -2,39 +2,97 @@ use crate::dictionary::Dictionary;
 use crate::styled_text::{Style, StyledText};
 use crossterm::style::Color;
 
+/// 穴埋めにした空欄の表示 (ヒントを表示しない場合)
+const BLANK: &str = "_____";
+
 pub fn style_example(dictionary: &Dictionary, example: &str, target: &str) -> Vec<StyledText> {
     let color = Color::DarkGrey;
-    example
-        .chars()
-        .fold(Vec::<String>::new(), |mut acc, c| {
-            if c.is_alphabetic() {
-                if let Some(last) = acc.last_mut() {
-                    if last.chars().all(|c| c.is_alphabetic() || c == '-') {
-                        *last = format!("{}{}", last, c);
-                        return acc;
-                    }
-                }
-                acc.push(c.to_string());
+    let target = dictionary.get_base_form(target);
+    split_words(example)
+        .into_iter()
+        .map(|word| {
+            if is_inflection_of(dictionary, word.as_str(), &target) {
+                StyledText::new(word.as_str(), color, Style::BoldUnderline)
             } else {
-                acc.push(c.to_string());
+                StyledText::new(word.as_str(), color, Style::Plain)
             }
-            acc
         })
+        .collect::<Vec<_>>()
+}
+
+/// 例文に `target` の活用形が含まれているか (`style_example` で強調される単語があるか) どうか
+/// `target` の原形が見つからない場合 (熟語など) は判定できないので None を返す
+pub fn has_inflection(dictionary: &Dictionary, example: &str, target: &str) -> Option<bool> {
+    let target = dictionary.get_base_form(target);
+    target.is_some().then(|| {
+        split_words(example)
+            .iter()
+            .any(|word| is_inflection_of(dictionary, word, &target))
+    })
+}
+
+/// 例文中の単語を空欄にした穴埋め問題
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cloze {
+    /// 空欄を含む例文
+    pub styled: Vec<StyledText>,
+    /// 空欄に入る単語 (例文中の順)
+    pub answers: Vec<String>,
+}
+
+/// 例文中の `target` の活用形を空欄にする
+/// # Arguments
+/// * `hint` - 空欄に頭文字と文字数を表示するかどうか
+pub fn cloze_example(dictionary: &Dictionary, example: &str, target: &str, hint: bool) -> Cloze {
+    let color = Color::DarkGrey;
+    let target = dictionary.get_base_form(target);
+    let mut answers = Vec::new();
+    let styled = split_words(example)
         .into_iter()
         .map(|word| {
-            if word.chars().all(|c| c.is_alphabetic() || c == '-') {
-                let word_in_sentence = dictionary.get_base_form(word.as_str());
-                let target_word = dictionary.get_base_form(target);
-                if word_in_sentence == target_word {
-                    StyledText::new(word.as_str(), color, Style::BoldUnderline)
+            if target.is_some() && is_inflection_of(dictionary, word.as_str(), &target) {
+                let blank = if hint {
+                    let mut chars = word.chars();
+                    let first = chars.next().map(String::from).unwrap_or_default();
+                    format!("{first}{}", "_".repeat(chars.count()))
                 } else {
-                    StyledText::new(word.as_str(), color, Style::Plain)
-                }
+                    BLANK.to_string()
+                };
+                answers.push(word);
+                StyledText::new(blank.as_str(), Color::Yellow, Style::Bold)
             } else {
                 StyledText::new(word.as_str(), color, Style::Plain)
             }
         })
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>();
+    Cloze { styled, answers }
+}
+
+/// 例文を単語 (ハイフンを含む) と、それ以外の 1 文字ずつに分割する
+fn split_words(example: &str) -> Vec<String> {
+    example.chars().fold(Vec::<String>::new(), |mut acc, c| {
+        if c.is_alphabetic() {
+            if let Some(last) = acc.last_mut()
+                && is_word(last)
+            {
+                *last = format!("{}{}", last, c);
+                return acc;
+            }
+            acc.push(c.to_string());
+        } else {
+            acc.push(c.to_string());
+        }
+        acc
+    })
+}
+
+fn is_word(token: &str) -> bool {
+    token.chars().all(|c| c.is_alphabetic() || c == '-')
+}
+
+/// `word` の原形が `target` (原形) と一致するかどうか
+fn is_inflection_of(dictionary: &Dictionary, word: &str, target: &Option<String>) -> bool {
+    is_word(word) && dictionary.get_base_form(word) == *target
 }
 
 #[cfg(test)]
@@ -218,4 +276,36 @@ mod tests {
         assert_eq!(styled[7], StyledText::new("?", color, Style::Plain));
         assert_eq!(styled[8], StyledText::new(":", color, Style::Plain));
     }
+
+    #[test]
+    fn test_cloze_example() {
+        let dictionary = Dictionary::new();
+        let example = "I ate a student.";
+        let color = Color::DarkGrey;
+        let cloze = cloze_example(&dictionary, example, "eat", false);
+
+        assert_eq!(cloze.answers, vec!["ate".to_string()]);
+        assert_eq!(cloze.styled[0], StyledText::new("I", color, Style::Plain));
+        assert_eq!(
+            cloze.styled[2],
+            StyledText::new("_____", Color::Yellow, Style::Bold)
+        );
+        assert_eq!(
+            cloze.styled[6],
+            StyledText::new("student", color, Style::Plain)
+        );
+    }
+
+    #[test]
+    fn test_cloze_example_with_hint() {
+        let dictionary = Dictionary::new();
+        let example = "She had a child.";
+        let cloze = cloze_example(&dictionary, example, "have", true);
+
+        assert_eq!(cloze.answers, vec!["had".to_string()]);
+        assert_eq!(
+            cloze.styled[2],
+            StyledText::new("h__", Color::Yellow, Style::Bold)
+        );
+    }
 }
@@ -0,0 +1,60 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 同じプロセスで作る一時ファイルの名前が重ならないようにする通し番号
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 一時ファイル (パッケージから取り出した SQLite のコレクションや、エディタで編集する単語など)
+/// 破棄する際に削除する
+pub struct TempFile(pub PathBuf);
+
+impl TempFile {
+    pub fn new(extension: &str) -> Self {
+        Self(temp_path(&format!(".{extension}")))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// テストで単語帳や進捗を置く一時ディレクトリ (破棄する際に中身ごと削除する)
+/// テストが途中で失敗しても、ファイルが残らない
+#[cfg(test)]
+pub struct TempDir(pub PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new() -> Self {
+        let dir = temp_path("");
+        fs::create_dir_all(&dir).expect("a temporary directory can be created");
+        Self(dir)
+    }
+
+    /// ディレクトリの中のファイルのパス
+    pub fn file(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 一時ファイルのパス (`vocab-<プロセス id>-<時刻>-<通し番号><suffix>`)
+fn temp_path(suffix: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.subsec_nanos());
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!("vocab-{}-{nanos}-{count}{suffix}", process::id()))
+}
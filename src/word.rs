@@ -0,0 +1,294 @@
+use crate::matcher::normalize;
+use crate::scheduler::{Grade, Schedule};
+use chrono::{Datelike, NaiveDate};
+use rand::{Rng, rng};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// 複数の日本語訳をまとめて表示する際の区切り文字
+const MEANING_SEPARATOR: &str = "、";
+
+/// 英単語の意味の 1 つ
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Sense {
+    /// 日本語訳
+    pub meaning: String,
+    /// 品詞 (例: noun, verb)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part_of_speech: Option<String>,
+    /// この意味で使った例文
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+    /// このバージョンの vocab が知らない項目 (書き出す際にそのまま残す)
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(from = "RawWord", into = "RawWord")]
+pub struct Word {
+    /// 単語帳の中で単語を識別する id (英単語を書き換えても変わらない)
+    /// 単語ファイルに書かれていない場合は空になり、`deck::assign_ids` で付ける
+    pub id: String,
+    /// 英単語
+    pub english: String,
+    /// 英単語の意味 (単語ファイルでの順)
+    pub senses: Vec<Sense>,
+    /// 単語を分類するタグ (例: toeic, phrasal)
+    pub tags: Vec<String>,
+    /// 学習スケジュール (未学習の単語は None)
+    /// 単語ファイルには書き出さず、進捗として保存する (単語ファイルにある場合は旧形式として読み込む)
+    pub schedule: Option<Schedule>,
+    /// 旧形式のスキップフラグ (読み込み時に `schedule` へ移行する)
+    pub skip: bool,
+    /// このバージョンの vocab が知らない項目 (書き出す際にそのまま残す)
+    pub extra: Map<String, Value>,
+}
+
+/// 単語ファイル上の単語
+/// 意味が 1 つだけの単語は、従来どおり `japanese` と `example` で書く
+#[derive(Deserialize, Serialize)]
+struct RawWord {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    id: String,
+    english: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    example: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    japanese: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    senses: Vec<Sense>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing)]
+    schedule: Option<Schedule>,
+    #[serde(default, skip_serializing)]
+    skip: bool,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl From<RawWord> for Word {
+    fn from(raw: RawWord) -> Self {
+        let mut senses = Vec::new();
+        if raw.japanese.is_some() || raw.example.is_some() {
+            senses.push(Sense {
+                meaning: raw.japanese.unwrap_or_default(),
+                part_of_speech: None,
+                examples: raw.example.into_iter().collect(),
+                extra: Map::new(),
+            });
+        }
+        senses.extend(raw.senses);
+        Self {
+            id: raw.id,
+            english: raw.english,
+            senses,
+            tags: raw.tags,
+            schedule: raw.schedule,
+            skip: raw.skip,
+            extra: raw.extra,
+        }
+    }
+}
+
+impl From<Word> for RawWord {
+    fn from(word: Word) -> Self {
+        let flat = word.is_flat();
+        let mut raw = RawWord {
+            id: word.id,
+            english: word.english,
+            example: None,
+            japanese: None,
+            senses: Vec::new(),
+            tags: word.tags,
+            schedule: word.schedule,
+            skip: false,
+            extra: word.extra,
+        };
+        if flat {
+            let sense = word
+                .senses
+                .into_iter()
+                .next()
+                .expect("a flat word has one sense");
+            raw.japanese = Some(sense.meaning);
+            raw.example = sense.examples.into_iter().next();
+        } else {
+            raw.senses = word.senses;
+        }
+        raw
+    }
+}
+
+/// 新しい単語の id (ランダムな 12 桁の 16 進数)
+pub fn generate_id() -> String {
+    format!("{:012x}", rng().random::<u64>() & 0xffff_ffff_ffff)
+}
+
+/// id のない単語に付ける id (表記ゆれを揃えた英単語のハッシュから作る 12 桁の 16 進数)
+/// 単語帳を共有する全員が同じ id を得られるよう、英単語だけから決める
+/// # Arguments
+/// * `attempt` - 他の単語の id と重なった場合に、別の id を作るための番号
+pub fn derive_id(english: &str, attempt: u32) -> String {
+    let mut key = normalize(english);
+    if attempt > 0 {
+        key.push_str(&format!("#{attempt}"));
+    }
+    format!("{:012x}", fnv1a(key.as_bytes()) & 0xffff_ffff_ffff)
+}
+
+/// 実行環境によらず同じ値になるハッシュ (FNV-1a, 64 ビット)
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+impl Word {
+    /// 意味が 1 つの単語を作る (例文が空の場合は例文なし)
+    pub fn new(english: &str, meaning: &str, example: &str) -> Self {
+        Self {
+            id: generate_id(),
+            english: english.to_string(),
+            senses: vec![Sense {
+                meaning: meaning.to_string(),
+                part_of_speech: None,
+                examples: (!example.is_empty())
+                    .then(|| example.to_string())
+                    .into_iter()
+                    .collect(),
+                extra: Map::new(),
+            }],
+            tags: Vec::new(),
+            schedule: None,
+            skip: false,
+            extra: Map::new(),
+        }
+    }
+
+    /// 従来の `japanese` と `example` だけの形式で書けるかどうか
+    pub fn is_flat(&self) -> bool {
+        match self.senses.as_slice() {
+            [sense] => sense.part_of_speech.is_none() && sense.examples.len() <= 1,
+            _ => false,
+        }
+    }
+
+    /// 全ての意味の日本語訳を区切り文字でつなげたもの
+    /// 入力した答えの判定では、いずれか 1 つと一致すれば正解になる
+    pub fn meaning(&self) -> String {
+        self.senses
+            .iter()
+            .map(|sense| sense.meaning.trim())
+            .filter(|meaning| !meaning.is_empty())
+            .collect::<Vec<_>>()
+            .join(MEANING_SEPARATOR)
+    }
+
+    /// 全ての意味の例文 (空の例文は除く)
+    pub fn examples(&self) -> impl Iterator<Item = &str> {
+        self.senses
+            .iter()
+            .flat_map(|sense| sense.examples.iter())
+            .map(String::as_str)
+            .filter(|example| !example.trim().is_empty())
+    }
+
+    /// 検索の対象にする文字列 (英単語・全ての日本語訳・全ての例文)
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.english.as_str())
+            .chain(self.senses.iter().map(|sense| sense.meaning.as_str()))
+            .chain(self.examples())
+    }
+
+    /// `today` に出題する例文 (例文が複数ある場合は日ごとに順番に切り替える)
+    /// 例文がない場合は空文字列を返す
+    pub fn example_on(&self, today: NaiveDate) -> &str {
+        let examples = self.examples().collect::<Vec<_>>();
+        if examples.is_empty() {
+            return "";
+        }
+        examples[today.num_days_from_ce().unsigned_abs() as usize % examples.len()]
+    }
+
+    /// 旧形式の `skip: true` を長い復習間隔のスケジュールに移行する
+    pub fn migrate_skip(&mut self, today: NaiveDate) {
+        if self.skip && self.schedule.is_none() {
+            self.schedule = Some(Schedule::memorized(today));
+        }
+        self.skip = false;
+    }
+
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        self.schedule
+            .as_ref()
+            .is_none_or(|schedule| schedule.is_due(today))
+    }
+
+    /// 出題の優先度 (未学習の単語は今日が期限のものと同じ扱い)
+    pub fn urgency(&self, today: NaiveDate) -> f64 {
+        self.schedule
+            .as_ref()
+            .map_or(0.0, |schedule| schedule.urgency(today))
+    }
+
+    pub fn grade(&mut self, grade: Grade, today: NaiveDate) {
+        self.schedule
+            .get_or_insert_with(|| Schedule::new(today))
+            .grade(grade, today);
+    }
+
+    /// 同じセッション内でもう一度出題した単語に自己評価をつける (易しさ係数は変えない)
+    pub fn relearn(&mut self, grade: Grade, today: NaiveDate) {
+        self.schedule
+            .get_or_insert_with(|| Schedule::new(today))
+            .relearn(grade, today);
+    }
+
+    pub fn mark_memorized(&mut self, today: NaiveDate) {
+        self.schedule = Some(Schedule::memorized(today));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_flat_and_senses() {
+        let json = r#"[
+            {"english": "run", "example": "I run.", "japanese": "走る"},
+            {"english": "book", "senses": [
+                {"meaning": "本", "part_of_speech": "noun", "examples": ["I read a book."]},
+                {"meaning": "予約する", "part_of_speech": "verb", "examples": ["Book a table.", "I booked it."]}
+            ]}
+        ]"#;
+        let words: Vec<Word> = serde_json::from_str(json).unwrap();
+
+        assert!(words[0].is_flat());
+        assert_eq!(words[0].meaning(), "走る");
+        assert!(!words[1].is_flat());
+        assert_eq!(words[1].meaning(), "本、予約する");
+        assert_eq!(words[1].examples().count(), 3);
+
+        // 意味が 1 つの単語は従来の形式のまま書き出す
+        let value = serde_json::to_value(&words).unwrap();
+        assert_eq!(
+            value[0],
+            serde_json::json!({"english": "run", "example": "I run.", "japanese": "走る"})
+        );
+        assert_eq!(value[1]["senses"][1]["examples"][1], "I booked it.");
+    }
+
+    #[test]
+    fn test_example_on_rotates() {
+        let mut word = Word::new("book", "本", "I read a book.");
+        word.senses[0].examples.push("Book a table.".to_string());
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let tomorrow = today.succ_opt().unwrap();
+
+        assert_ne!(word.example_on(today), word.example_on(tomorrow));
+        assert_eq!(Word::new("run", "走る", "").example_on(today), "");
+    }
+}